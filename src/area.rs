@@ -0,0 +1,257 @@
+use crate::fill::get_closing_curve;
+use crate::path::{get_subpaths, PathSegment};
+use crate::vector::{Arc, Curve, Vector2};
+
+// SVG has +y downward, so a positive signed area turns clockwise on screen
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+}
+
+impl Orientation {
+    pub fn from_area(area: f64) -> Option<Self> {
+        if area > 0.0 {
+            Some(Orientation::Clockwise)
+        } else if area < 0.0 {
+            Some(Orientation::CounterClockwise)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SubpathArea {
+    pub area: f64,
+    // `None` when the area is zero
+    pub centroid: Option<Vector2>,
+    pub orientation: Option<Orientation>,
+}
+
+// Open subpaths are closed by a line as fill does
+pub fn get_subpath_areas(segments: &[PathSegment]) -> Vec<SubpathArea> {
+    get_subpaths(segments)
+        .iter()
+        .map(|subpath| {
+            // Measuring around the start keeps precision for far away shapes
+            let origin = subpath.start;
+            let mut curves: Vec<Curve> = subpath.curves.iter().map(|(_, c)| *c).collect();
+            curves.extend(get_closing_curve(subpath));
+
+            let (area, moment) = curves
+                .iter()
+                .map(|c| get_curve_moments(c, origin))
+                .fold((0.0, Vector2(0.0, 0.0)), |acc, v| {
+                    (acc.0 + v.0, acc.1 + v.1)
+                });
+
+            SubpathArea {
+                area,
+                centroid: if area == 0.0 {
+                    None
+                } else {
+                    Some(moment.multi(1.0 / area) + origin)
+                },
+                orientation: Orientation::from_area(area),
+            }
+        })
+        .collect()
+}
+
+// Sum of the signed areas of the subpaths
+pub fn get_area(segments: &[PathSegment]) -> f64 {
+    get_subpath_areas(segments).iter().map(|s| s.area).sum()
+}
+
+// Centroid of the subpaths weighted by their signed areas, so that holes
+// wound the other way are subtracted
+pub fn get_centroid(segments: &[PathSegment]) -> Option<Vector2> {
+    let (area, moment) = get_subpath_areas(segments)
+        .iter()
+        .filter_map(|s| s.centroid.map(|c| (s.area, c.multi(s.area))))
+        .fold((0.0, Vector2(0.0, 0.0)), |acc, v| {
+            (acc.0 + v.0, acc.1 + v.1)
+        });
+    if area == 0.0 {
+        None
+    } else {
+        Some(moment.multi(1.0 / area))
+    }
+}
+
+// Signed area and its first moment of the region swept by the segment from
+// `origin`: the area is 1/2 * int(P x P') and the moment is
+// 1/3 * int(P * (P x P')), since the thin triangle (origin, P, P + dP) has
+// its centroid at 2/3 * P.
+fn get_curve_moments(curve: &Curve, origin: Vector2) -> (f64, Vector2) {
+    match curve {
+        Curve::Line(c) => get_polynomial_moments(&[c.p0() - origin, c.p1() - c.p0()]),
+        Curve::Bezier2(c) => {
+            let mut coeffs = c.get_power_coeffs().to_vec();
+            coeffs[0] = coeffs[0] - origin;
+            get_polynomial_moments(&coeffs)
+        }
+        Curve::Bezier3(c) => {
+            let mut coeffs = c.get_power_coeffs().to_vec();
+            coeffs[0] = coeffs[0] - origin;
+            get_polynomial_moments(&coeffs)
+        }
+        Curve::Arc(c) => get_arc_moments(c, origin),
+    }
+}
+
+// `coeffs` are ascending power basis coefficients of P(t), t in [0, 1]
+fn get_polynomial_moments(coeffs: &[Vector2]) -> (f64, Vector2) {
+    let x: Vec<f64> = coeffs.iter().map(|c| c.0).collect();
+    let y: Vec<f64> = coeffs.iter().map(|c| c.1).collect();
+    let cross = sub_polynomial(
+        &mul_polynomial(&x, &derive_polynomial(&y)),
+        &mul_polynomial(&y, &derive_polynomial(&x)),
+    );
+
+    (
+        integrate_polynomial(&cross) / 2.0,
+        Vector2(
+            integrate_polynomial(&mul_polynomial(&x, &cross)),
+            integrate_polynomial(&mul_polynomial(&y, &cross)),
+        )
+        .multi(1.0 / 3.0),
+    )
+}
+
+// P(r) = c + U cos(r) + V sin(r), so P x P' = a sin(r) + b cos(r) + g
+fn get_arc_moments(arc: &Arc, origin: Vector2) -> (f64, Vector2) {
+    let rot = arc.rotation();
+    let c = arc.center() - origin;
+    let u = Vector2(arc.rx(), 0.0).rotate(rot);
+    let v = Vector2(0.0, arc.ry()).rotate(rot);
+    let a = -c.cross(u);
+    let b = c.cross(v);
+    let g = u.cross(v);
+
+    let (r0, r1) = (arc.theta(), arc.theta() + arc.dtheta());
+    let dr = r1 - r0;
+    let i_sin = r0.cos() - r1.cos();
+    let i_cos = r1.sin() - r0.sin();
+    let i_sin_cos = (r1.sin().powi(2) - r0.sin().powi(2)) / 2.0;
+    let i_sin2 = ((2.0 * r1).sin() - (2.0 * r0).sin()) / 4.0;
+    let i_cos_cos = dr / 2.0 + i_sin2;
+    let i_sin_sin = dr / 2.0 - i_sin2;
+
+    let area = (a * i_sin + b * i_cos + g * dr) / 2.0;
+    let moment = c.multi(a * i_sin + b * i_cos + g * dr)
+        + u.multi(a * i_sin_cos + b * i_cos_cos + g * i_cos)
+        + v.multi(a * i_sin_sin + b * i_sin_cos + g * i_sin);
+    (area, moment.multi(1.0 / 3.0))
+}
+
+fn mul_polynomial(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut ret = vec![0.0; a.len() + b.len() - 1];
+    for (i, u) in a.iter().enumerate() {
+        for (j, v) in b.iter().enumerate() {
+            ret[i + j] += u * v;
+        }
+    }
+    ret
+}
+
+fn sub_polynomial(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut ret = vec![0.0; a.len().max(b.len())];
+    for (i, u) in a.iter().enumerate() {
+        ret[i] += u;
+    }
+    for (i, v) in b.iter().enumerate() {
+        ret[i] -= v;
+    }
+    ret
+}
+
+fn derive_polynomial(a: &[f64]) -> Vec<f64> {
+    if a.len() <= 1 {
+        return vec![0.0];
+    }
+    a.iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| c * i as f64)
+        .collect()
+}
+
+// Integral over [0, 1]
+fn integrate_polynomial(a: &[f64]) -> f64 {
+    a.iter().enumerate().map(|(i, c)| c / (i + 1) as f64).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::test_utils::assert_close;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn get_subpath_areas_polygon() {
+        let ret = get_subpath_areas(&parse("M10 10 h20 v10 h-20 z"));
+        assert_eq!(ret.len(), 1);
+        assert_close(ret[0].area, 200.0);
+        assert_eq!(ret[0].centroid, Some(Vector2(20.0, 15.0)));
+        assert_eq!(ret[0].orientation, Some(Orientation::Clockwise));
+
+        // Implicitly closed, wound the other way
+        let ret = get_subpath_areas(&parse("M0 0 v10 h10"));
+        assert_close(ret[0].area, -50.0);
+        let c = ret[0].centroid.unwrap();
+        assert_close(c.0, 10.0 / 3.0);
+        assert_close(c.1, 20.0 / 3.0);
+        assert_eq!(ret[0].orientation, Some(Orientation::CounterClockwise));
+
+        let ret = get_subpath_areas(&parse("M0 0 M5 5 h10"));
+        assert_eq!(ret.len(), 2);
+        assert_eq!(ret[0].area, 0.0);
+        assert_eq!(ret[0].centroid, None);
+        assert_eq!(ret[1].orientation, None);
+    }
+
+    #[test]
+    fn get_subpath_areas_curves() {
+        // Parabola segment: 2/3 of the bounding triangle
+        let ret = get_subpath_areas(&parse("M0 0 Q5 10 10 0 z"));
+        assert_close(ret[0].area, -100.0 / 3.0);
+        let c = ret[0].centroid.unwrap();
+        assert_close(c.0, 5.0);
+        assert_close(c.1, 2.0);
+
+        // int(y dx) with x = 30t^2 - 20t^3 and y = 30t(1 - t)
+        let ret = get_subpath_areas(&parse("M0 0 C0 10 10 10 10 0 z"));
+        assert_close(ret[0].area, -60.0);
+
+        let circle = get_subpath_areas(&parse(
+            "M110 100 A10 10 0 0 1 90 100 A10 10 0 0 1 110 100 z",
+        ));
+        assert_close(circle[0].area, 100.0 * PI);
+        let c = circle[0].centroid.unwrap();
+        assert_close(c.0, 100.0);
+        assert_close(c.1, 100.0);
+
+        let ellipse = get_subpath_areas(&parse("M0 20 A20 10 90 0 0 0 -20 A20 10 90 0 0 0 20 z"));
+        assert_close(ellipse[0].area.abs(), 200.0 * PI);
+
+        // Half disk: centroid is 4r / 3pi away from the center
+        let half = get_subpath_areas(&parse("M10 0 A10 10 0 0 1 -10 0 z"));
+        assert_close(half[0].area, 50.0 * PI);
+        let c = half[0].centroid.unwrap();
+        assert_close(c.0, 0.0);
+        assert_close(c.1, 40.0 / (3.0 * PI));
+    }
+
+    #[test]
+    fn get_area_and_centroid_with_hole() {
+        let d = parse("M0 0 h30 v30 h-30 z M10 10 v10 h10 v-10 z");
+        assert_close(get_area(&d), 800.0);
+        let c = get_centroid(&d).unwrap();
+        assert_close(c.0, 15.0);
+        assert_close(c.1, 15.0);
+        assert_eq!(get_centroid(&parse("M0 0 h10")), None);
+    }
+}
@@ -0,0 +1,576 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use crate::fill::{get_closing_curve, get_curve_winding_number, FillRule};
+use crate::intersection::intersect_curves;
+use crate::keyword::Keyword;
+use crate::path::{from_closed_curves, get_subpaths, PathSegment};
+use crate::vector::{Arc, Curve, Lerpable, Line, Vector2};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BooleanOp {
+    Union,
+    Intersection,
+    Difference,
+    Xor,
+}
+
+impl Keyword for BooleanOp {
+    const KEYWORDS: &'static [(&'static str, Self)] = &[
+        ("union", BooleanOp::Union),
+        ("intersection", BooleanOp::Intersection),
+        ("difference", BooleanOp::Difference),
+        ("xor", BooleanOp::Xor),
+    ];
+}
+
+impl BooleanOp {
+    fn is_inside(self, a: bool, b: bool) -> bool {
+        match self {
+            BooleanOp::Union => a || b,
+            BooleanOp::Intersection => a && b,
+            BooleanOp::Difference => a && !b,
+            BooleanOp::Xor => a != b,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Fill<'a> {
+    pub segments: &'a [PathSegment],
+    pub rule: FillRule,
+}
+
+// The outline of the region where `op` holds for the fills.
+// Outer boundaries are wound clockwise on screen and holes the other way.
+pub fn get_boolean(a: Fill, b: Fill, op: BooleanOp) -> Vec<PathSegment> {
+    let curves_a = get_bounded_curves(a.segments);
+    let curves_b = get_bounded_curves(b.segments);
+    let curves: Vec<Curve> = curves_a.iter().chain(&curves_b).map(|(c, _)| *c).collect();
+    get_region_outline(&curves, |p| {
+        op.is_inside(
+            a.rule.is_inside(get_winding_number(&curves_a, p)),
+            b.rule.is_inside(get_winding_number(&curves_b, p)),
+        )
+    })
+}
+
+// The outline of the region bounded by `curves` where `is_inside` holds.
+// Curves are split at crossings and kept as they are, and every piece is
+// classified by sampling the region on both of its sides. Coincident pieces
+// therefore get the same classification and only one of them is kept.
+pub(crate) fn get_region_outline(
+    curves: &[Curve],
+    is_inside: impl Fn(Vector2) -> bool,
+) -> Vec<PathSegment> {
+    if curves.is_empty() {
+        return vec![];
+    }
+
+    let bounds: Vec<(Vector2, Vector2)> = curves.iter().map(|c| c.get_bounds()).collect();
+    let scale = get_scale(&bounds);
+    let tolerance = scale * 1e-7;
+
+    let mut vertices = Vertices::new(tolerance);
+    let mut edges: Vec<Edge> = vec![];
+    // Edges by their end vertices, to find duplicates
+    let mut by_ends: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (curve, ts) in curves.iter().zip(get_split_t(curves, &bounds, tolerance)) {
+        for w in ts.windows(2) {
+            let piece = curve.subcurve(w[0], w[1]);
+            let from = vertices.get(piece.start());
+            let to = vertices.get(piece.end());
+            let mid = piece.lerp(0.5);
+            if from == to && (mid - piece.start()).norm() <= tolerance {
+                continue;
+            }
+
+            let d = piece.derivative(0.5);
+            if d.norm() == 0.0 {
+                continue;
+            }
+            let n = Vector2(-d.1, d.0).multi(scale * 1e-6 / d.norm());
+            let (right, left) = (is_inside(mid + n), is_inside(mid - n));
+            if right == left {
+                continue;
+            }
+
+            // Keeps the filled side on `n`
+            let edge = if right {
+                Edge {
+                    curve: piece,
+                    from,
+                    to,
+                    mid,
+                }
+            } else {
+                Edge {
+                    curve: piece.subcurve(1.0, 0.0),
+                    from: to,
+                    to: from,
+                    mid,
+                }
+            };
+            let same_ends = by_ends.entry((edge.from, edge.to)).or_default();
+            let duplicated = same_ends
+                .iter()
+                .any(|i| (edges[*i].mid - edge.mid).norm() <= scale * 1e-6);
+            if !duplicated {
+                same_ends.push(edges.len());
+                edges.push(edge);
+            }
+        }
+    }
+
+    link_edges(&edges, &vertices.points)
+}
+
+struct Edge {
+    curve: Curve,
+    from: usize,
+    to: usize,
+    mid: Vector2,
+}
+
+// Curves of every subpath including the lines closing open ones, with
+// their bounds
+fn get_bounded_curves(segments: &[PathSegment]) -> Vec<(Curve, (Vector2, Vector2))> {
+    let mut ret = vec![];
+    for subpath in get_subpaths(segments) {
+        let closing = get_closing_curve(&subpath);
+        ret.extend(
+            subpath
+                .curves
+                .iter()
+                .map(|(_, c)| *c)
+                .chain(closing)
+                .map(|c| (c, c.get_bounds())),
+        );
+    }
+    ret
+}
+
+// Same as `fill::get_winding_number` on the path of the curves, skipping
+// the curves that the ray misses
+fn get_winding_number(curves: &[(Curve, (Vector2, Vector2))], p: Vector2) -> i32 {
+    curves
+        .iter()
+        .filter(|(_, (min, max))| min.1 <= p.1 && p.1 <= max.1 && p.0 < max.0)
+        .map(|(c, _)| get_curve_winding_number(c, p))
+        .sum()
+}
+
+fn get_scale(bounds: &[(Vector2, Vector2)]) -> f64 {
+    let (min, max) = bounds.iter().fold(
+        (
+            Vector2(f64::INFINITY, f64::INFINITY),
+            Vector2(f64::NEG_INFINITY, f64::NEG_INFINITY),
+        ),
+        |(min, max), (a, b)| {
+            (
+                Vector2(min.0.min(a.0), min.1.min(a.1)),
+                Vector2(max.0.max(b.0), max.1.max(b.1)),
+            )
+        },
+    );
+    (max - min).norm().max(1e-9)
+}
+
+// Sorted parameters splitting each curve at crossings, and at the vertices
+// of the other curves lying on it so that coincident edges share their ends
+fn get_split_t(curves: &[Curve], bounds: &[(Vector2, Vector2)], tolerance: f64) -> Vec<Vec<f64>> {
+    let mut ret: Vec<Vec<f64>> = vec![vec![0.0, 1.0]; curves.len()];
+    for (i, curve) in curves.iter().enumerate() {
+        if let Curve::Bezier3(c) = curve {
+            if let Some((s, t)) = c.get_self_intersection_t() {
+                ret[i].extend([s, t]);
+            }
+        }
+    }
+    for (i, j) in get_overlapping_pairs(bounds, tolerance) {
+        for (s, t) in intersect_curves(&curves[i], &curves[j]) {
+            ret[i].push(s);
+            ret[j].push(t);
+        }
+        for (a, b) in [(i, j), (j, i)] {
+            for p in [curves[b].start(), curves[b].end()] {
+                let t = curves[a].get_closest_t(p);
+                if (curves[a].lerp(t) - p).norm() <= tolerance {
+                    ret[a].push(t);
+                }
+            }
+        }
+    }
+
+    for ts in ret.iter_mut() {
+        ts.sort_by(|a, b| a.total_cmp(b));
+        ts.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+        // The ends stay exact after dedup
+        if let Some(last) = ts.last_mut() {
+            *last = 1.0;
+        }
+    }
+    ret
+}
+
+// Sorted index pairs of the curves whose bounds overlap within `tolerance`,
+// found by sweeping the bounds along x
+fn get_overlapping_pairs(bounds: &[(Vector2, Vector2)], tolerance: f64) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..bounds.len()).collect();
+    order.sort_by(|a, b| bounds[*a].0 .0.total_cmp(&bounds[*b].0 .0));
+
+    let mut ret = vec![];
+    for (k, &i) in order.iter().enumerate() {
+        let (min, max) = bounds[i];
+        for &j in &order[(k + 1)..] {
+            let (other_min, other_max) = bounds[j];
+            if other_min.0 > max.0 + tolerance {
+                break;
+            }
+            if other_min.1 <= max.1 + tolerance && min.1 <= other_max.1 + tolerance {
+                ret.push((i.min(j), i.max(j)));
+            }
+        }
+    }
+    ret.sort_unstable();
+    ret
+}
+
+// Points within `tolerance` of each other share an index. They're hashed on
+// a grid of that size, so only the neighboring cells are searched.
+struct Vertices {
+    points: Vec<Vector2>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    tolerance: f64,
+}
+
+impl Vertices {
+    fn new(tolerance: f64) -> Self {
+        Vertices {
+            points: vec![],
+            cells: HashMap::new(),
+            tolerance,
+        }
+    }
+
+    fn get(&mut self, p: Vector2) -> usize {
+        let (x, y) = self.get_cell(p);
+        let found = (x.saturating_sub(1)..=x.saturating_add(1))
+            .flat_map(|x| (y.saturating_sub(1)..=y.saturating_add(1)).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|i| (self.points[*i] - p).norm() <= self.tolerance)
+            .min();
+        if let Some(i) = found {
+            return i;
+        }
+
+        self.points.push(p);
+        self.cells
+            .entry((x, y))
+            .or_default()
+            .push(self.points.len() - 1);
+        self.points.len() - 1
+    }
+
+    fn get_cell(&self, p: Vector2) -> (i64, i64) {
+        (
+            (p.0 / self.tolerance).floor() as i64,
+            (p.1 / self.tolerance).floor() as i64,
+        )
+    }
+}
+
+// Every edge belongs to some loop since each vertex has as many incoming
+// edges as outgoing ones. How the loops are chosen at shared vertices
+// doesn't change the winding numbers.
+fn link_edges(edges: &[Edge], vertices: &[Vector2]) -> Vec<PathSegment> {
+    let mut used = vec![false; edges.len()];
+    let mut ret: Vec<PathSegment> = vec![];
+
+    let mut outgoing: Vec<Vec<usize>> = vec![vec![]; vertices.len()];
+    for (i, e) in edges.iter().enumerate() {
+        outgoing[e.from].push(i);
+    }
+    // The outgoing edges before the cursor of a vertex are all used
+    let mut cursors = vec![0; vertices.len()];
+
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+
+        let start = edges[first].from;
+        let mut curves: Vec<Curve> = vec![];
+        let mut current = first;
+        loop {
+            used[current] = true;
+            let e = &edges[current];
+            curves.push(snap(e.curve, vertices[e.from], vertices[e.to]));
+            if e.to == start {
+                break;
+            }
+            let (next, cursor) = (&outgoing[e.to], &mut cursors[e.to]);
+            while *cursor < next.len() && used[next[*cursor]] {
+                *cursor += 1;
+            }
+            match next.get(*cursor) {
+                Some(i) => current = *i,
+                None => break,
+            }
+        }
+
+        ret.extend(from_closed_curves(&merge_lines(curves)));
+    }
+    ret
+}
+
+fn snap(curve: Curve, p0: Vector2, p1: Vector2) -> Curve {
+    match curve {
+        Curve::Line(mut c) => {
+            c.p0 = p0;
+            c.p1 = p1;
+            Curve::Line(c)
+        }
+        Curve::Bezier2(mut c) => {
+            c.p0 = p0;
+            c.p2 = p1;
+            Curve::Bezier2(c)
+        }
+        Curve::Bezier3(mut c) => {
+            c.p0 = p0;
+            c.p3 = p1;
+            Curve::Bezier3(c)
+        }
+        // The center has to move with the end points, so the arc is rebuilt
+        Curve::Arc(c) if p0 != p1 => Curve::Arc(Arc::new(
+            p0,
+            c.rx,
+            c.ry,
+            c.rotation.to_degrees(),
+            c.dtheta.abs() > PI,
+            c.dtheta > 0.0,
+            p1,
+        )),
+        Curve::Arc(mut c) => {
+            c.p0 = p0;
+            c.p1 = p1;
+            Curve::Arc(c)
+        }
+    }
+}
+
+// Joins lines split at the vertices of the other curves, including the
+// lines meeting at the start of the loop
+fn merge_lines(curves: Vec<Curve>) -> Vec<Curve> {
+    let mut ret: Vec<Curve> = vec![];
+    for c in curves {
+        if let (Some(Curve::Line(prev)), Curve::Line(next)) = (ret.last_mut(), c) {
+            if is_continued(prev, &next) {
+                prev.p1 = next.p1;
+                continue;
+            }
+        }
+        ret.push(c);
+    }
+
+    if ret.len() > 2 {
+        if let (Curve::Line(last), Curve::Line(first)) = (ret[ret.len() - 1], ret[0]) {
+            if is_continued(&last, &first) {
+                ret[0] = Curve::Line(Line::new(last.p0, first.p1));
+                ret.pop();
+            }
+        }
+    }
+    ret
+}
+
+fn is_continued(a: &Line, b: &Line) -> bool {
+    let d0 = a.p1 - a.p0;
+    let d1 = b.p1 - b.p0;
+    d0.cross(d1).abs() <= 1e-9 * d0.norm() * d1.norm() && d0.dot(d1) > 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::get_area;
+    use crate::parser::parse;
+    use crate::serializer::serialize;
+    use crate::test_utils::assert_close;
+    use std::f64::consts::PI;
+
+    fn run(a: &str, b: &str, op: BooleanOp) -> Vec<PathSegment> {
+        let a = parse(a);
+        let b = parse(b);
+        get_boolean(
+            Fill {
+                segments: &a,
+                rule: FillRule::NonZero,
+            },
+            Fill {
+                segments: &b,
+                rule: FillRule::NonZero,
+            },
+            op,
+        )
+    }
+
+    const A: &str = "M0 0 h20 v20 h-20 z";
+    const B: &str = "M10 10 h20 v20 h-20 z";
+
+    #[test]
+    fn boolean_op_parse_cases() {
+        assert_eq!(BooleanOp::parse("xor"), Some(BooleanOp::Xor));
+        assert_eq!(BooleanOp::parse("subtract"), None);
+    }
+
+    #[test]
+    fn get_boolean_polygons() {
+        let ret = run(A, B, BooleanOp::Union);
+        assert_eq!(
+            serialize(&ret),
+            "M0 0 L20 0 L20 10 L30 10 L30 30 L10 30 L10 20 L0 20 Z"
+        );
+        assert_close(get_area(&ret), 700.0);
+
+        let ret = run(A, B, BooleanOp::Intersection);
+        assert_eq!(serialize(&ret), "M20 10 L20 20 L10 20 L10 10 Z");
+        assert_close(get_area(&run(A, B, BooleanOp::Difference)), 300.0);
+        assert_close(get_area(&run(A, B, BooleanOp::Xor)), 600.0);
+
+        // Disjoint and contained
+        let ret = run(A, "M30 0 h10 v10 h-10 z", BooleanOp::Union);
+        assert_close(get_area(&ret), 500.0);
+        assert_eq!(
+            run(A, "M30 0 h10 v10 h-10 z", BooleanOp::Intersection),
+            vec![]
+        );
+        let ret = run(A, "M5 5 h10 v10 h-10 z", BooleanOp::Difference);
+        assert_eq!(
+            serialize(&ret),
+            "M0 0 L20 0 L20 20 L0 20 Z M15 5 L5 5 L5 15 L15 15 Z"
+        );
+        assert_close(get_area(&ret), 300.0);
+    }
+
+    #[test]
+    fn get_boolean_coincident_edges() {
+        // Sharing a whole side
+        let ret = run(A, "M20 0 h20 v20 h-20 z", BooleanOp::Union);
+        assert_eq!(serialize(&ret), "M0 0 L40 0 L40 20 L0 20 Z");
+
+        // Sharing a part of a side, wound the other way
+        let ret = run(A, "M20 5 v10 h10 v-10 z", BooleanOp::Union);
+        assert_close(get_area(&ret), 500.0);
+        assert_eq!(ret.iter().filter(|s| s._type == 'M').count(), 1);
+
+        assert_eq!(run(A, A, BooleanOp::Xor), vec![]);
+        assert_eq!(
+            serialize(&run(A, A, BooleanOp::Union)),
+            "M0 0 L20 0 L20 20 L0 20 Z"
+        );
+    }
+
+    #[test]
+    fn get_boolean_curves() {
+        let circle = "M10 0 A10 10 0 0 1 -10 0 A10 10 0 0 1 10 0 z";
+        let ret = run(circle, "M0 -20 h20 v40 h-20 z", BooleanOp::Difference);
+        assert!(ret.iter().any(|s| s._type == 'A'));
+        assert_close(get_area(&ret), 50.0 * PI);
+
+        let ret = run(circle, "M0 -20 h20 v40 h-20 z", BooleanOp::Intersection);
+        assert_close(get_area(&ret), 50.0 * PI);
+
+        let ret = run(
+            "M0 0 C0 -20 20 -20 20 0 z",
+            "M10 -20 h20 v40 h-20 z",
+            BooleanOp::Union,
+        );
+        assert!(ret.iter().any(|s| s._type == 'C'));
+        // The left half of the cubic's area, plus the rectangle
+        assert_close(get_area(&ret), 120.0 + 800.0);
+    }
+
+    #[test]
+    fn get_boolean_fill_rules() {
+        let a = parse("M0 0 h30 v30 h-30 z M10 10 h10 v10 h-10 z");
+        let b = parse("M40 0 h10 v10 h-10 z");
+        let union = |rule| {
+            get_area(&get_boolean(
+                Fill { segments: &a, rule },
+                Fill {
+                    segments: &b,
+                    rule: FillRule::NonZero,
+                },
+                BooleanOp::Union,
+            ))
+        };
+        assert_close(union(FillRule::NonZero), 1000.0);
+        assert_close(union(FillRule::EvenOdd), 900.0);
+    }
+
+    #[test]
+    fn get_boolean_many_curves() {
+        // 200 squares, the right half of them under a rectangle
+        let a: String = (0..200)
+            .map(|i| format!("M{} 0 h5 v5 h-5 z", i * 10))
+            .collect();
+        let b = "M1000 -5 h1000 v15 h-1000 z";
+        assert_close(get_area(&run(&a, b, BooleanOp::Union)), 2500.0 + 15000.0);
+        let ret = run(&a, b, BooleanOp::Difference);
+        assert_close(get_area(&ret), 2500.0);
+        assert_eq!(ret.iter().filter(|s| s._type == 'M').count(), 100);
+    }
+
+    #[test]
+    fn get_overlapping_pairs_cases() {
+        let bounds = [
+            (Vector2(0.0, 0.0), Vector2(10.0, 10.0)),
+            (Vector2(20.0, 0.0), Vector2(30.0, 10.0)),
+            (Vector2(5.0, 5.0), Vector2(25.0, 6.0)),
+            (Vector2(5.0, 20.0), Vector2(25.0, 30.0)),
+            (Vector2(10.5, 0.0), Vector2(19.5, 10.0)),
+        ];
+        assert_eq!(
+            get_overlapping_pairs(&bounds, 1.0),
+            vec![(0, 2), (0, 4), (1, 2), (1, 4), (2, 4)]
+        );
+        assert_eq!(
+            get_overlapping_pairs(&bounds, 0.0),
+            vec![(0, 2), (1, 2), (2, 4)]
+        );
+    }
+
+    #[test]
+    fn vertices_cases() {
+        let mut vertices = Vertices::new(0.1);
+        assert_eq!(vertices.get(Vector2(1.0, 1.0)), 0);
+        assert_eq!(vertices.get(Vector2(1.05, 0.95)), 0);
+        assert_eq!(vertices.get(Vector2(1.0, 1.2)), 1);
+        // Across a cell boundary
+        assert_eq!(vertices.get(Vector2(-0.01, 0.0)), 2);
+        assert_eq!(vertices.get(Vector2(0.01, 0.0)), 2);
+        assert_eq!(vertices.get(Vector2(f64::NAN, 0.0)), 3);
+    }
+
+    #[test]
+    fn snap_arc() {
+        let arc = Curve::Arc(Arc::new(
+            Vector2(10.0, 0.0),
+            10.0,
+            10.0,
+            0.0,
+            false,
+            true,
+            Vector2(-10.0, 0.0),
+        ));
+        let p0 = Vector2(10.0, 1e-6);
+        let p1 = Vector2(-10.0, -1e-6);
+        let ret = snap(arc, p0, p1);
+        assert_close(ret.lerp(0.0), p0);
+        assert_close(ret.lerp(1.0), p1);
+        assert!((ret.lerp(0.5) - Vector2(0.0, 10.0)).norm() < 1e-5);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::path::PathSegment;
+use crate::serializer::serialize;
+
+// Builds segments with the right number of parameters for each command.
+// Methods prefixed with `rel_` make relative commands.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathBuilder {
+    segments: Vec<PathSegment>,
+}
+
+impl PathBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn build(&self) -> Vec<PathSegment> {
+        self.segments.clone()
+    }
+
+    pub fn serialize(&self) -> String {
+        serialize(&self.segments)
+    }
+
+    pub fn move_to(&mut self, x: f64, y: f64) -> &mut Self {
+        self.push('M', vec![x, y])
+    }
+
+    pub fn rel_move_to(&mut self, dx: f64, dy: f64) -> &mut Self {
+        self.push('m', vec![dx, dy])
+    }
+
+    pub fn line_to(&mut self, x: f64, y: f64) -> &mut Self {
+        self.push('L', vec![x, y])
+    }
+
+    pub fn rel_line_to(&mut self, dx: f64, dy: f64) -> &mut Self {
+        self.push('l', vec![dx, dy])
+    }
+
+    pub fn h(&mut self, x: f64) -> &mut Self {
+        self.push('H', vec![x])
+    }
+
+    pub fn rel_h(&mut self, dx: f64) -> &mut Self {
+        self.push('h', vec![dx])
+    }
+
+    pub fn v(&mut self, y: f64) -> &mut Self {
+        self.push('V', vec![y])
+    }
+
+    pub fn rel_v(&mut self, dy: f64) -> &mut Self {
+        self.push('v', vec![dy])
+    }
+
+    pub fn quad_to(&mut self, x1: f64, y1: f64, x: f64, y: f64) -> &mut Self {
+        self.push('Q', vec![x1, y1, x, y])
+    }
+
+    pub fn rel_quad_to(&mut self, dx1: f64, dy1: f64, dx: f64, dy: f64) -> &mut Self {
+        self.push('q', vec![dx1, dy1, dx, dy])
+    }
+
+    pub fn smooth_quad_to(&mut self, x: f64, y: f64) -> &mut Self {
+        self.push('T', vec![x, y])
+    }
+
+    pub fn rel_smooth_quad_to(&mut self, dx: f64, dy: f64) -> &mut Self {
+        self.push('t', vec![dx, dy])
+    }
+
+    pub fn cubic_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x: f64, y: f64) -> &mut Self {
+        self.push('C', vec![x1, y1, x2, y2, x, y])
+    }
+
+    pub fn rel_cubic_to(
+        &mut self,
+        dx1: f64,
+        dy1: f64,
+        dx2: f64,
+        dy2: f64,
+        dx: f64,
+        dy: f64,
+    ) -> &mut Self {
+        self.push('c', vec![dx1, dy1, dx2, dy2, dx, dy])
+    }
+
+    pub fn smooth_cubic_to(&mut self, x2: f64, y2: f64, x: f64, y: f64) -> &mut Self {
+        self.push('S', vec![x2, y2, x, y])
+    }
+
+    pub fn rel_smooth_cubic_to(&mut self, dx2: f64, dy2: f64, dx: f64, dy: f64) -> &mut Self {
+        self.push('s', vec![dx2, dy2, dx, dy])
+    }
+
+    // `rotation` is in degrees as the 'A' command
+    #[allow(clippy::too_many_arguments)]
+    pub fn arc_to(
+        &mut self,
+        rx: f64,
+        ry: f64,
+        rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        x: f64,
+        y: f64,
+    ) -> &mut Self {
+        self.push(
+            'A',
+            get_arc_values(rx, ry, rotation, large_arc, sweep, x, y),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn rel_arc_to(
+        &mut self,
+        rx: f64,
+        ry: f64,
+        rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        dx: f64,
+        dy: f64,
+    ) -> &mut Self {
+        self.push(
+            'a',
+            get_arc_values(rx, ry, rotation, large_arc, sweep, dx, dy),
+        )
+    }
+
+    pub fn close(&mut self) -> &mut Self {
+        self.push('Z', vec![])
+    }
+
+    fn push(&mut self, _type: char, values: Vec<f64>) -> &mut Self {
+        self.segments.push(PathSegment::new(_type, values));
+        self
+    }
+}
+
+fn get_arc_values(
+    rx: f64,
+    ry: f64,
+    rotation: f64,
+    large_arc: bool,
+    sweep: bool,
+    x: f64,
+    y: f64,
+) -> Vec<f64> {
+    let flag = |b: bool| if b { 1.0 } else { 0.0 };
+    vec![rx, ry, rotation, flag(large_arc), flag(sweep), x, y]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn path_builder_cases() {
+        let mut b = PathBuilder::new();
+        b.move_to(1.0, 2.0)
+            .line_to(3.0, 4.0)
+            .h(5.0)
+            .v(6.0)
+            .quad_to(1.0, 2.0, 3.0, 4.0)
+            .smooth_quad_to(5.0, 6.0)
+            .cubic_to(1.0, 2.0, 3.0, 4.0, 5.0, 6.0)
+            .smooth_cubic_to(1.0, 2.0, 3.0, 4.0)
+            .arc_to(1.0, 2.0, 30.0, true, false, 3.0, 4.0)
+            .close();
+        let d = "M1 2 L3 4 H5 V6 Q1 2 3 4 T5 6 C1 2 3 4 5 6 S1 2 3 4 A1 2 30 1 0 3 4 Z";
+        assert_eq!(b.serialize(), d);
+        assert_eq!(b.build(), parse(d));
+
+        let mut b = PathBuilder::new();
+        b.rel_move_to(1.0, 2.0)
+            .rel_line_to(3.0, 4.0)
+            .rel_h(5.0)
+            .rel_v(6.0)
+            .rel_quad_to(1.0, 2.0, 3.0, 4.0)
+            .rel_smooth_quad_to(5.0, 6.0)
+            .rel_cubic_to(1.0, 2.0, 3.0, 4.0, 5.0, 6.0)
+            .rel_smooth_cubic_to(1.0, 2.0, 3.0, 4.0)
+            .rel_arc_to(1.0, 2.0, 30.0, false, true, 3.0, 4.0)
+            .close();
+        assert_eq!(
+            b.serialize(),
+            "m1 2 l3 4 h5 v6 q1 2 3 4 t5 6 c1 2 3 4 5 6 s1 2 3 4 a1 2 30 0 1 3 4 Z"
+        );
+
+        assert_eq!(PathBuilder::new().build(), vec![]);
+    }
+}
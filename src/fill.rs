@@ -0,0 +1,171 @@
+use crate::keyword::{Keyword, Property};
+use crate::path::{get_subpaths, PathSegment, Subpath};
+use crate::solver::bisect;
+use crate::vector::{Curve, Lerpable, Line, Vector2};
+
+// https://www.w3.org/TR/SVG2/painting.html#FillRuleProperty
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+impl Keyword for FillRule {
+    const KEYWORDS: &'static [(&'static str, Self)] = &[
+        ("nonzero", FillRule::NonZero),
+        ("evenodd", FillRule::EvenOdd),
+    ];
+}
+
+impl Property for FillRule {
+    const DEFAULT: Self = FillRule::NonZero;
+}
+
+impl FillRule {
+    pub fn is_inside(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+pub fn is_point_in_fill(segments: &[PathSegment], p: Vector2, rule: FillRule) -> bool {
+    rule.is_inside(get_winding_number(segments, p))
+}
+
+// Counts crossings of the ray from `p` toward +x. Crossings in +y direction
+// count as +1 and the others as -1.
+pub fn get_winding_number(segments: &[PathSegment], p: Vector2) -> i32 {
+    get_subpaths(segments)
+        .iter()
+        .map(|s| get_subpath_winding_number(s, p))
+        .sum()
+}
+
+pub(crate) fn get_subpath_winding_number(subpath: &Subpath, p: Vector2) -> i32 {
+    let mut ret: i32 = subpath
+        .curves
+        .iter()
+        .map(|(_, c)| get_curve_winding_number(c, p))
+        .sum();
+
+    if let Some(c) = get_closing_curve(subpath) {
+        ret += get_curve_winding_number(&c, p);
+    }
+    ret
+}
+
+// Open subpaths are filled as if they were closed by a line
+pub(crate) fn get_closing_curve(subpath: &Subpath) -> Option<Curve> {
+    match (subpath.closed, subpath.curves.last()) {
+        (false, Some((_, last))) => Some(Curve::Line(Line::new(last.end(), subpath.start))),
+        _ => None,
+    }
+}
+
+pub(crate) fn get_curve_winding_number(curve: &Curve, p: Vector2) -> i32 {
+    let mut ts = vec![0.0];
+    ts.extend(curve.get_extrema_t(Vector2(0.0, 1.0)));
+    ts.push(1.0);
+
+    let point_at = |t: f64| {
+        // Exact end points keep the crossings at shared vertices consistent
+        if t == 0.0 {
+            curve.start()
+        } else if t == 1.0 {
+            curve.end()
+        } else {
+            curve.lerp(t)
+        }
+    };
+
+    let mut ret = 0;
+    for w in ts.windows(2) {
+        let (t0, t1) = (w[0], w[1]);
+        let (y0, y1) = (point_at(t0).1, point_at(t1).1);
+        if y0 == y1 {
+            continue;
+        }
+
+        // Half-open range so that a vertex on the ray is counted once
+        let (lo, hi) = if y0 < y1 { (y0, y1) } else { (y1, y0) };
+        if p.1 < lo || hi <= p.1 {
+            continue;
+        }
+
+        let t = bisect(|t| point_at(t).1 - p.1, t0, t1);
+        if p.0 < curve.lerp(t).0 {
+            ret += if y0 < y1 { 1 } else { -1 };
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn inside(d: &str, x: f64, y: f64, rule: FillRule) -> bool {
+        is_point_in_fill(&parse(d), Vector2(x, y), rule)
+    }
+
+    #[test]
+    fn fill_rule_parse_cases() {
+        assert_eq!(FillRule::parse("nonzero"), Some(FillRule::NonZero));
+        assert_eq!(FillRule::parse("evenodd"), Some(FillRule::EvenOdd));
+        assert_eq!(FillRule::parse("other"), None);
+    }
+
+    #[test]
+    fn get_winding_number_cases() {
+        let square = parse("M0 0 L10 0 L10 10 L0 10 Z");
+        assert_eq!(get_winding_number(&square, Vector2(5.0, 5.0)).abs(), 1);
+        assert_eq!(get_winding_number(&square, Vector2(15.0, 5.0)), 0);
+        assert_eq!(get_winding_number(&square, Vector2(-5.0, 5.0)), 0);
+        assert_eq!(
+            get_winding_number(&square, Vector2(5.0, 0.0)).abs(),
+            1,
+            "should count a vertex on the ray once"
+        );
+
+        let twice = parse("M0 0 L10 0 L10 10 L0 10 Z M0 0 L10 0 L10 10 L0 10 Z");
+        assert_eq!(get_winding_number(&twice, Vector2(5.0, 5.0)).abs(), 2);
+        let reversed = parse("M0 0 L10 0 L10 10 L0 10 Z M0 0 L0 10 L10 10 L10 0 Z");
+        assert_eq!(get_winding_number(&reversed, Vector2(5.0, 5.0)), 0);
+    }
+
+    #[test]
+    fn is_point_in_fill_rules() {
+        let d = "M0 0 h30 v30 h-30 z M10 10 h10 v10 h-10 z";
+        assert!(inside(d, 15.0, 15.0, FillRule::NonZero));
+        assert!(!inside(d, 15.0, 15.0, FillRule::EvenOdd));
+        assert!(inside(d, 5.0, 5.0, FillRule::EvenOdd));
+    }
+
+    #[test]
+    fn is_point_in_fill_open_subpath() {
+        assert!(inside("M0 0 L10 0 L10 10", 8.0, 2.0, FillRule::NonZero));
+        assert!(!inside("M0 0 L10 0 L10 10", 2.0, 8.0, FillRule::NonZero));
+    }
+
+    #[test]
+    fn is_point_in_fill_curves() {
+        // The chord from (0, 0) to (10, 10) closes the curve
+        let q = "M0 0 Q10 0 10 10";
+        assert!(inside(q, 7.0, 3.0, FillRule::NonZero));
+        assert!(!inside(q, 9.9, 1.0, FillRule::NonZero));
+
+        let c = "M0 0 C0 -10 10 -10 10 0 Z";
+        assert!(inside(c, 5.0, -7.0, FillRule::NonZero));
+        assert!(!inside(c, 5.0, -7.6, FillRule::NonZero));
+
+        let circle = "M0 -10 A10 10 0 0 1 0 10 A10 10 0 0 1 0 -10 Z";
+        assert!(inside(circle, 0.0, 0.0, FillRule::NonZero));
+        assert!(inside(circle, 7.0, 7.0, FillRule::NonZero));
+        assert!(!inside(circle, 7.2, 7.2, FillRule::NonZero));
+        assert!(inside(circle, -9.9, 0.0, FillRule::NonZero));
+        assert!(!inside(circle, -10.1, 0.0, FillRule::NonZero));
+    }
+}
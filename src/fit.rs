@@ -0,0 +1,248 @@
+use crate::path::PathSegment;
+use crate::vector::{Bezier3, Curve, Lerpable, Vector2};
+
+// Fits cubic Bezier curves to the points so that every point is within
+// `error` from them, returning 'M' followed by 'C' only. The curves are split
+// where the polyline turns more than `corner_angle` in radians, otherwise
+// they're joined smoothly.
+// Ref: Philip J. Schneider, "An Algorithm for Automatically Fitting Digitized
+// Curves", Graphics Gems, 1990
+pub fn fit_curves(points: &[Vector2], error: f64, corner_angle: f64) -> Vec<PathSegment> {
+    let mut points = points.to_vec();
+    points.dedup();
+    if points.is_empty() {
+        return vec![];
+    }
+
+    let mut ret = vec![PathSegment::new('M', vec![points[0].0, points[0].1])];
+    let mut curves: Vec<Bezier3> = vec![];
+    let mut first = 0;
+    for last in 1..points.len() {
+        if last == points.len() - 1 || is_corner(&points, last, corner_angle) {
+            let piece = &points[first..=last];
+            let t1 = get_unit(piece[1] - piece[0]);
+            let t2 = get_unit(piece[piece.len() - 2] - piece[piece.len() - 1]);
+            fit_cubic(piece, t1, t2, error, &mut curves);
+            first = last;
+        }
+    }
+    ret.extend(
+        curves
+            .iter()
+            .map(|c| PathSegment::from_curve(&Curve::Bezier3(*c))),
+    );
+    ret
+}
+
+fn is_corner(points: &[Vector2], i: usize, corner_angle: f64) -> bool {
+    let a = points[i] - points[i - 1];
+    let b = points[i + 1] - points[i];
+    a.cross(b).atan2(a.dot(b)).abs() > corner_angle
+}
+
+fn fit_cubic(points: &[Vector2], t1: Vector2, t2: Vector2, error: f64, ret: &mut Vec<Bezier3>) {
+    let n = points.len();
+    let (p0, p3) = (points[0], points[n - 1]);
+    if n == 2 {
+        let d = (p3 - p0).norm() / 3.0;
+        ret.push(Bezier3::new(p0, p0 + t1.multi(d), p3 + t2.multi(d), p3));
+        return;
+    }
+
+    let mut u = get_chord_length_params(points);
+    let mut bezier = generate_bezier(points, &u, t1, t2);
+    let (mut max, mut split) = get_max_error(points, &bezier, &u);
+    if max <= error {
+        ret.push(bezier);
+        return;
+    }
+
+    // Close enough to be worth moving the parameters onto the curve
+    if max <= error * 4.0 {
+        for _ in 0..4 {
+            u = reparameterize(points, &bezier, &u);
+            bezier = generate_bezier(points, &u, t1, t2);
+            let (m, s) = get_max_error(points, &bezier, &u);
+            max = m;
+            split = s;
+            if max <= error {
+                ret.push(bezier);
+                return;
+            }
+        }
+    }
+
+    // Both halves share the tangent at the split point to join smoothly
+    let center = get_unit(points[split - 1] - points[split + 1]);
+    let center = if center == Vector2(0.0, 0.0) {
+        get_unit(points[split - 1] - points[split])
+    } else {
+        center
+    };
+    fit_cubic(&points[..=split], t1, center, error, ret);
+    fit_cubic(&points[split..], center.multi(-1.0), t2, error, ret);
+}
+
+fn get_chord_length_params(points: &[Vector2]) -> Vec<f64> {
+    let mut u = vec![0.0];
+    for i in 1..points.len() {
+        u.push(u[i - 1] + (points[i] - points[i - 1]).norm());
+    }
+    let total = u[u.len() - 1];
+    u.iter().map(|v| v / total).collect()
+}
+
+// Least squares for the lengths of the handles along the end tangents
+fn generate_bezier(points: &[Vector2], u: &[f64], t1: Vector2, t2: Vector2) -> Bezier3 {
+    let (p0, p3) = (points[0], points[points.len() - 1]);
+    let mut c = [[0.0; 2]; 2];
+    let mut x = [0.0; 2];
+    for (p, &t) in points.iter().zip(u) {
+        let s = 1.0 - t;
+        let (b0, b1, b2, b3) = (s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t);
+        let a1 = t1.multi(b1);
+        let a2 = t2.multi(b2);
+        c[0][0] += a1.dot(a1);
+        c[0][1] += a1.dot(a2);
+        c[1][1] += a2.dot(a2);
+        let tmp = *p - (p0.multi(b0 + b1) + p3.multi(b2 + b3));
+        x[0] += a1.dot(tmp);
+        x[1] += a2.dot(tmp);
+    }
+
+    let det = c[0][0] * c[1][1] - c[0][1] * c[0][1];
+    let (alpha_l, alpha_r) = if det.abs() > f64::EPSILON {
+        (
+            (x[0] * c[1][1] - x[1] * c[0][1]) / det,
+            (c[0][0] * x[1] - c[0][1] * x[0]) / det,
+        )
+    } else {
+        (0.0, 0.0)
+    };
+
+    // Handles pointing backward or collapsing are replaced with the heuristic
+    let chord = (p3 - p0).norm();
+    let eps = chord * 1e-6;
+    let (alpha_l, alpha_r) = if alpha_l < eps || alpha_r < eps {
+        (chord / 3.0, chord / 3.0)
+    } else {
+        (alpha_l, alpha_r)
+    };
+    Bezier3::new(p0, p0 + t1.multi(alpha_l), p3 + t2.multi(alpha_r), p3)
+}
+
+// One Newton step towards the closest parameter for each point
+fn reparameterize(points: &[Vector2], bezier: &Bezier3, u: &[f64]) -> Vec<f64> {
+    let [_, a1, a2, a3] = bezier.get_power_coeffs();
+    points
+        .iter()
+        .zip(u)
+        .map(|(p, &t)| {
+            let d = bezier.lerp(t) - *p;
+            let d1 = a1 + a2.multi(2.0 * t) + a3.multi(3.0 * t * t);
+            let d2 = a2.multi(2.0) + a3.multi(6.0 * t);
+            let den = d1.dot(d1) + d.dot(d2);
+            if den == 0.0 {
+                t
+            } else {
+                (t - d.dot(d1) / den).clamp(0.0, 1.0)
+            }
+        })
+        .collect()
+}
+
+// The largest distance and the index of its interior point
+fn get_max_error(points: &[Vector2], bezier: &Bezier3, u: &[f64]) -> (f64, usize) {
+    let mut ret = (0.0, points.len() / 2);
+    for i in 1..(points.len() - 1) {
+        let d = (bezier.lerp(u[i]) - points[i]).norm();
+        if d > ret.0 {
+            ret = (d, i);
+        }
+    }
+    ret
+}
+
+fn get_unit(v: Vector2) -> Vector2 {
+    let n = v.norm();
+    if n == 0.0 {
+        v
+    } else {
+        v.multi(1.0 / n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::path::get_closest_point;
+    use crate::serializer::serialize;
+    use std::f64::consts::PI;
+
+    fn assert_within(ret: &[PathSegment], points: &[Vector2], error: f64) {
+        for p in points {
+            let closest = get_closest_point(ret, *p).unwrap();
+            assert!(closest.distance <= error + 1e-9, "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn fit_curves_cases() {
+        assert_eq!(fit_curves(&[], 1.0, PI / 4.0), vec![]);
+        assert_eq!(
+            serialize(&fit_curves(
+                &[Vector2(1.0, 2.0), Vector2(1.0, 2.0)],
+                1.0,
+                PI / 4.0
+            )),
+            "M1 2"
+        );
+
+        // Points on a line make a single flat curve
+        let line: Vec<Vector2> = (0..=10).map(|i| Vector2(i as f64, 0.0)).collect();
+        assert_eq!(
+            serialize(&fit_curves(&line, 0.1, PI / 4.0)),
+            "M0 0 C3.3333333333333335 0 6.666666666666666 0 10 0"
+        );
+
+        // Points on a cubic curve are fitted by a single one
+        let bezier = Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(0.0, 10.0),
+            Vector2(20.0, 10.0),
+            Vector2(20.0, 0.0),
+        );
+        let points: Vec<Vector2> = (0..=100).map(|i| bezier.lerp(i as f64 / 100.0)).collect();
+        let ret = fit_curves(&points, 0.1, PI / 4.0);
+        assert_eq!(ret.len(), 2);
+        assert_within(&ret, &points, 0.1);
+    }
+
+    #[test]
+    fn fit_curves_corners() {
+        // Corners are kept as they are
+        let mut points: Vec<Vector2> = (0..=10).map(|i| Vector2(i as f64, 0.0)).collect();
+        points.extend((1..=10).map(|i| Vector2(10.0, i as f64)));
+        let ret = fit_curves(&points, 0.1, PI / 4.0);
+        assert_eq!(ret.len(), 3);
+        assert_eq!(ret[1].values[4..], [10.0, 0.0]);
+
+        // A circle is split into smooth curves
+        let points: Vec<Vector2> = (0..=100)
+            .map(|i| Vector2(10.0, 0.0).rotate(2.0 * PI * i as f64 / 100.0))
+            .collect();
+        let ret = fit_curves(&points, 0.05, PI / 4.0);
+        assert!(ret.len() > 2 && ret.len() < 10);
+        assert!(ret[1..].iter().all(|s| s._type == 'C'));
+        assert_within(&ret, &points, 0.05);
+        let curves = parse(&serialize(&ret));
+        for i in 1..(curves.len() - 1) {
+            let a = &curves[i].values;
+            let b = &curves[i + 1].values;
+            let d0 = Vector2(a[4] - a[2], a[5] - a[3]);
+            let d1 = Vector2(b[0] - a[4], b[1] - a[5]);
+            assert!(d0.cross(d1).abs() < 1e-9 * d0.norm() * d1.norm());
+        }
+    }
+}
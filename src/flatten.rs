@@ -0,0 +1,104 @@
+use crate::path::{get_subpaths, PathSegment};
+use crate::vector::{Curve, Vector2};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    // The start point isn't repeated at the end of closed ones
+    pub points: Vec<Vector2>,
+    pub closed: bool,
+}
+
+// Polylines of the subpaths deviating from the curves by at most
+// `tolerance`. Curves are subdivided at their middles until flat, so that
+// straight parts get few points and tight bends get many. Nothing is
+// returned unless `tolerance` is positive.
+pub fn flatten(segments: &[PathSegment], tolerance: f64) -> Vec<Polyline> {
+    if tolerance.is_nan() || tolerance <= 0.0 {
+        return vec![];
+    }
+    get_subpaths(segments)
+        .iter()
+        .filter(|s| !s.curves.is_empty())
+        .map(|subpath| {
+            let mut points = vec![subpath.start];
+            for (_, curve) in &subpath.curves {
+                append_flattened(&mut points, curve, tolerance, 0);
+            }
+            if subpath.closed && points.len() > 1 {
+                points.pop();
+            }
+            Polyline {
+                points,
+                closed: subpath.closed,
+            }
+        })
+        .collect()
+}
+
+// Appends the points after the start of the curve
+fn append_flattened(points: &mut Vec<Vector2>, curve: &Curve, tolerance: f64, depth: usize) {
+    if depth >= 16 || curve.is_flat(tolerance) {
+        points.push(curve.end());
+        return;
+    }
+    let (a, b) = curve.split_at(0.5);
+    append_flattened(points, &a, tolerance, depth + 1);
+    append_flattened(points, &b, tolerance, depth + 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn flatten_lines() {
+        assert_eq!(
+            flatten(&parse("M0 0 h10 v10 M20 0 h10 v10 z M5 5"), 0.1),
+            vec![
+                Polyline {
+                    points: vec![Vector2(0.0, 0.0), Vector2(10.0, 0.0), Vector2(10.0, 10.0)],
+                    closed: false,
+                },
+                Polyline {
+                    points: vec![Vector2(20.0, 0.0), Vector2(30.0, 0.0), Vector2(30.0, 10.0)],
+                    closed: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn flatten_curves() {
+        let circle = parse("M10 0 A10 10 0 0 1 -10 0 A10 10 0 0 1 10 0 z");
+        let coarse = flatten(&circle, 1.0);
+        let fine = flatten(&circle, 0.01);
+        assert!(coarse[0].closed);
+        assert!(coarse[0].points.len() < fine[0].points.len());
+        for polyline in [&coarse[0], &fine[0]] {
+            for p in &polyline.points {
+                assert!((p.norm() - 10.0).abs() < 1e-9);
+            }
+        }
+        // Each chord's middle stays within the tolerance
+        let ps = &fine[0].points;
+        for i in 0..ps.len() {
+            let m = (ps[i] + ps[(i + 1) % ps.len()]).multi(0.5);
+            assert!(10.0 - m.norm() <= 0.01);
+        }
+
+        // The middle of an S curve is flat, its bends aren't
+        let ret = flatten(&parse("M0 0 C20 0 -10 10 10 10"), 0.05);
+        assert_eq!(ret[0].points[0], Vector2(0.0, 0.0));
+        assert_eq!(ret[0].points[ret[0].points.len() - 1], Vector2(10.0, 10.0));
+        assert!(ret[0].points.len() > 4);
+    }
+
+    #[test]
+    fn flatten_invalid_tolerance() {
+        let circle = parse("M10 0 A10 10 0 0 1 -10 0 z");
+        assert_eq!(flatten(&circle, 0.0), vec![]);
+        assert_eq!(flatten(&circle, -1.0), vec![]);
+        assert_eq!(flatten(&circle, f64::NAN), vec![]);
+    }
+}
@@ -0,0 +1,344 @@
+use std::convert::TryFrom;
+
+use crate::path::PathSegment;
+use crate::segment::Segment;
+use crate::vector::{Arc, Bezier2, Bezier3, Curve, Line, Vector2};
+
+// A segment resolved into absolute coordinates
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Primitive {
+    // Starts a subpath, also yielded when drawing begins without a moveto
+    MoveTo(Vector2),
+    Line(Line),
+    Bezier2(Bezier2),
+    Bezier3(Bezier3),
+    Arc(Arc),
+    // The line back to the start of the subpath
+    Close(Line),
+}
+
+impl Primitive {
+    pub fn to_curve(&self) -> Option<Curve> {
+        match *self {
+            Primitive::MoveTo(_) => None,
+            Primitive::Line(c) | Primitive::Close(c) => Some(Curve::Line(c)),
+            Primitive::Bezier2(c) => Some(Curve::Bezier2(c)),
+            Primitive::Bezier3(c) => Some(Curve::Bezier3(c)),
+            Primitive::Arc(c) => Some(Curve::Arc(c)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Geometry {
+    // Index of the segment it came from
+    pub index: usize,
+    // Start point of the subpath it belongs to
+    pub start: Vector2,
+    pub primitive: Primitive,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum State {
+    // No subpath yet
+    Empty,
+    Open,
+    Closed,
+}
+
+// Resolves relative coordinates, reflected control points and implicit
+// subpaths as SVG does. Arcs ending at their start are skipped, and ones
+// without radii become lines.
+#[derive(Debug, Clone)]
+pub struct GeometryIter<I> {
+    segments: I,
+    // Number of segments read so far
+    cursor: usize,
+    start: Vector2,
+    current: Vector2,
+    // Only the previous segment's control point can be reflected
+    control_q: Option<Vector2>,
+    control_c: Option<Vector2>,
+    state: State,
+    pending: Option<Geometry>,
+}
+
+// Same as `GeometryIter` for path segments. Segments that aren't valid,
+// with unknown commands or missing values, are skipped keeping the indices
+// into `segments`.
+pub fn iter_geometry(segments: &[PathSegment]) -> impl Iterator<Item = Geometry> + '_ {
+    let valid = segments.iter().filter_map(|s| Segment::try_from(s).ok());
+    let mut indices = (0..segments.len()).filter(move |&i| Segment::try_from(&segments[i]).is_ok());
+    // Count of the valid segments passed, and the index of the last one
+    let (mut count, mut index) = (0, 0);
+    GeometryIter::new(valid).map(move |mut g| {
+        while count <= g.index {
+            index = indices.next().unwrap();
+            count += 1;
+        }
+        g.index = index;
+        g
+    })
+}
+
+impl<I: Iterator<Item = Segment>> GeometryIter<I> {
+    pub fn new(segments: I) -> Self {
+        GeometryIter {
+            segments,
+            cursor: 0,
+            start: Vector2(0.0, 0.0),
+            current: Vector2(0.0, 0.0),
+            control_q: None,
+            control_c: None,
+            state: State::Empty,
+            pending: None,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Segment>> Iterator for GeometryIter<I> {
+    type Item = Geometry;
+
+    fn next(&mut self) -> Option<Geometry> {
+        if let Some(g) = self.pending.take() {
+            return Some(g);
+        }
+
+        for seg in &mut self.segments {
+            let index = self.cursor;
+            self.cursor += 1;
+            let current = self.current;
+            let base = if seg.is_absolute() {
+                Vector2(0.0, 0.0)
+            } else {
+                current
+            };
+            let prev_q = self.control_q.take();
+            let prev_c = self.control_c.take();
+
+            let primitive = match seg {
+                Segment::MoveTo { to, .. } => {
+                    return Some(self.move_to(index, to + base));
+                }
+                // A leading lineto acts as a moveto
+                Segment::LineTo { to, .. } if self.state == State::Empty => {
+                    return Some(self.move_to(index, to));
+                }
+                Segment::Close { .. } => {
+                    self.current = self.start;
+                    if self.state != State::Open {
+                        continue;
+                    }
+                    self.state = State::Closed;
+                    return Some(Geometry {
+                        index,
+                        start: self.start,
+                        primitive: Primitive::Close(Line::new(current, self.start)),
+                    });
+                }
+                Segment::LineTo { to, .. } => Primitive::Line(Line::new(current, to + base)),
+                Segment::HorizontalTo { x, .. } => {
+                    Primitive::Line(Line::new(current, Vector2(x + base.0, current.1)))
+                }
+                Segment::VerticalTo { y, .. } => {
+                    Primitive::Line(Line::new(current, Vector2(current.0, y + base.1)))
+                }
+                Segment::QuadTo { c, to, .. } => {
+                    let c = c + base;
+                    self.control_q = Some(c);
+                    Primitive::Bezier2(Bezier2::new(current, c, to + base))
+                }
+                Segment::SmoothQuadTo { to, .. } => {
+                    let c = current.multi(2.0) - prev_q.unwrap_or(current);
+                    self.control_q = Some(c);
+                    Primitive::Bezier2(Bezier2::new(current, c, to + base))
+                }
+                Segment::CubicTo { c1, c2, to, .. } => {
+                    let c2 = c2 + base;
+                    self.control_c = Some(c2);
+                    Primitive::Bezier3(Bezier3::new(current, c1 + base, c2, to + base))
+                }
+                Segment::SmoothCubicTo { c2, to, .. } => {
+                    let c2 = c2 + base;
+                    self.control_c = Some(c2);
+                    Primitive::Bezier3(Bezier3::new(
+                        current,
+                        current.multi(2.0) - prev_c.unwrap_or(current),
+                        c2,
+                        to + base,
+                    ))
+                }
+                Segment::ArcTo {
+                    rx,
+                    ry,
+                    rotation,
+                    large,
+                    sweep,
+                    to,
+                    ..
+                } => {
+                    let to = to + base;
+                    if to == current {
+                        // https://www.w3.org/TR/SVG11/implnote.html#ArcOutOfRangeParameters
+                        continue;
+                    }
+                    if rx == 0.0 || ry == 0.0 {
+                        Primitive::Line(Line::new(current, to))
+                    } else {
+                        Primitive::Arc(Arc::new(current, rx, ry, rotation, large, sweep, to))
+                    }
+                }
+            };
+
+            if let Some(curve) = primitive.to_curve() {
+                self.current = curve.end();
+            }
+            let g = Geometry {
+                index,
+                start: self.start,
+                primitive,
+            };
+            // Drawing after a closepath starts a new subpath at the same point
+            if self.state != State::Open {
+                self.state = State::Open;
+                self.pending = Some(g);
+                return Some(Geometry {
+                    index,
+                    start: self.start,
+                    primitive: Primitive::MoveTo(self.start),
+                });
+            }
+            return Some(g);
+        }
+        None
+    }
+}
+
+impl<I> GeometryIter<I> {
+    fn move_to(&mut self, index: usize, p: Vector2) -> Geometry {
+        self.start = p;
+        self.current = p;
+        self.state = State::Open;
+        Geometry {
+            index,
+            start: p,
+            primitive: Primitive::MoveTo(p),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, parse_segments};
+
+    fn primitives(d: &str) -> Vec<(usize, Primitive)> {
+        iter_geometry(&parse(d))
+            .map(|g| (g.index, g.primitive))
+            .collect()
+    }
+
+    #[test]
+    fn iter_geometry_cases() {
+        let v = |x: f64, y: f64| Vector2(x, y);
+        assert_eq!(
+            primitives("M1 2 h3 V5 l-3 0 z"),
+            vec![
+                (0, Primitive::MoveTo(v(1.0, 2.0))),
+                (1, Primitive::Line(Line::new(v(1.0, 2.0), v(4.0, 2.0)))),
+                (2, Primitive::Line(Line::new(v(4.0, 2.0), v(4.0, 5.0)))),
+                (3, Primitive::Line(Line::new(v(4.0, 5.0), v(1.0, 5.0)))),
+                (4, Primitive::Close(Line::new(v(1.0, 5.0), v(1.0, 2.0)))),
+            ]
+        );
+
+        // Reflections only follow the same kind of curves
+        assert_eq!(
+            primitives("M0 0 C1 1 2 2 3 3 s4 4 5 5 Q1 1 2 2 S3 3 4 4 T5 5")[2..],
+            [
+                (
+                    2,
+                    Primitive::Bezier3(Bezier3::new(
+                        v(3.0, 3.0),
+                        v(4.0, 4.0),
+                        v(7.0, 7.0),
+                        v(8.0, 8.0)
+                    ))
+                ),
+                (
+                    3,
+                    Primitive::Bezier2(Bezier2::new(v(8.0, 8.0), v(1.0, 1.0), v(2.0, 2.0)))
+                ),
+                (
+                    4,
+                    Primitive::Bezier3(Bezier3::new(
+                        v(2.0, 2.0),
+                        v(2.0, 2.0),
+                        v(3.0, 3.0),
+                        v(4.0, 4.0)
+                    ))
+                ),
+                (
+                    5,
+                    Primitive::Bezier2(Bezier2::new(v(4.0, 4.0), v(4.0, 4.0), v(5.0, 5.0)))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_geometry_subpaths() {
+        let ret: Vec<Geometry> =
+            iter_geometry(&parse("M1 1 h1 z z l1 0 A1 1 0 0 1 2 1 A0 1 0 0 1 3 3")).collect();
+        assert_eq!(ret.len(), 6);
+        // Drawing after 'Z' starts a subpath without moveto
+        assert_eq!(ret[3].index, 4);
+        assert_eq!(ret[3].primitive, Primitive::MoveTo(Vector2(1.0, 1.0)));
+        assert_eq!(ret[4].start, Vector2(1.0, 1.0));
+        // The arc to the current point is skipped, the one without radii is a line
+        assert_eq!(ret[5].index, 6);
+        assert_eq!(
+            ret[5].primitive,
+            Primitive::Line(Line::new(Vector2(2.0, 1.0), Vector2(3.0, 3.0)))
+        );
+
+        // A leading lineto is a moveto
+        assert_eq!(
+            primitives("L1 2"),
+            vec![(0, Primitive::MoveTo(Vector2(1.0, 2.0)))]
+        );
+
+        // Unknown commands are skipped
+        let segments = [
+            PathSegment::new('M', vec![0.0, 0.0]),
+            PathSegment::new('K', vec![1.0]),
+            PathSegment::new('L', vec![1.0, 1.0]),
+        ];
+        let ret: Vec<Geometry> = iter_geometry(&segments).collect();
+        assert_eq!(ret.len(), 2);
+        assert_eq!(ret[1].index, 2);
+    }
+
+    #[test]
+    fn geometry_iter_segments() {
+        let segments = parse_segments(b"M1 2 h3 z").map(Result::unwrap);
+        let ret: Vec<(usize, Primitive)> = GeometryIter::new(segments)
+            .map(|g| (g.index, g.primitive))
+            .collect();
+        assert_eq!(ret, primitives("M1 2 h3 z"));
+    }
+
+    #[test]
+    fn iter_geometry_lack_of_parameter() {
+        // Skipped like unknown commands
+        let segments = [
+            PathSegment::new('M', vec![0.0, 0.0]),
+            PathSegment::new('L', vec![1.0]),
+            PathSegment::new('C', vec![1.0]),
+            PathSegment::new('L', vec![1.0, 1.0]),
+        ];
+        let ret: Vec<Geometry> = iter_geometry(&segments).collect();
+        assert_eq!(ret.len(), 2);
+        assert_eq!(ret[1].index, 3);
+    }
+}
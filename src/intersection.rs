@@ -0,0 +1,453 @@
+use std::f64::consts::PI;
+
+use crate::path::{get_subpaths, PathSegment, SPLIT_COUNT};
+use crate::solver::solve_polynomial;
+use crate::vector::{Arc, Curve, Lerpable, Line, Vector2};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Intersection {
+    pub point: Vector2,
+    // Indices of the segments, 'Z' for closing lines
+    pub index_a: usize,
+    pub index_b: usize,
+    // Parameters on the segments' curves
+    pub t_a: f64,
+    pub t_b: f64,
+    // Distances from the starts of the paths
+    pub length_a: f64,
+    pub length_b: f64,
+}
+
+// Crossing points of two paths sorted along `a`.
+// Overlapping stretches of the paths aren't reported, and crossings at the
+// same point are reported once.
+pub fn get_intersections(a: &[PathSegment], b: &[PathSegment]) -> Vec<Intersection> {
+    let curves_a = get_measured_curves(a);
+    let curves_b = get_measured_curves(b);
+    let mut ret: Vec<Intersection> = vec![];
+
+    for (index_a, ca, offset_a) in &curves_a {
+        for (index_b, cb, offset_b) in &curves_b {
+            for (t_a, t_b) in intersect_curves(ca, cb) {
+                let item = Intersection {
+                    point: ca.lerp(t_a),
+                    index_a: *index_a,
+                    index_b: *index_b,
+                    t_a,
+                    t_b,
+                    length_a: offset_a + ca.get_appro_length_at_t(t_a, SPLIT_COUNT),
+                    length_b: offset_b + cb.get_appro_length_at_t(t_b, SPLIT_COUNT),
+                };
+                // Crossings at vertices are found on both adjacent segments
+                let duplicated = ret.iter().any(|r| (r.point - item.point).norm() < 1e-9);
+                if !duplicated {
+                    ret.push(item);
+                }
+            }
+        }
+    }
+
+    ret.sort_by(|x, y| x.length_a.partial_cmp(&y.length_a).unwrap());
+    ret
+}
+
+// Points where the path crosses itself, within a cubic loop or between two
+// curves. Each item has `length_a <= length_b`.
+pub fn get_self_intersections(segments: &[PathSegment]) -> Vec<Intersection> {
+    let mut curves: Vec<(usize, Curve, f64)> = vec![];
+    // Pairs of positions in `curves` joined at a vertex
+    let mut adjacent: Vec<(usize, usize)> = vec![];
+    let mut offset = 0.0;
+    for subpath in get_subpaths(segments) {
+        let first = curves.len();
+        for (k, (index, curve)) in subpath.curves.iter().enumerate() {
+            if k > 0 {
+                adjacent.push((curves.len() - 1, curves.len()));
+            }
+            curves.push((*index, *curve, offset));
+            offset += curve.get_appro_length(SPLIT_COUNT);
+        }
+        if subpath.closed && curves.len() - first > 1 {
+            adjacent.push((first, curves.len() - 1));
+        }
+    }
+
+    let mut ret: Vec<Intersection> = vec![];
+    let mut push = |item: Intersection| {
+        if !ret.iter().any(|r| (r.point - item.point).norm() < 1e-9) {
+            ret.push(item);
+        }
+    };
+    let measure = |(index, curve, offset): &(usize, Curve, f64), t: f64| {
+        (*index, offset + curve.get_appro_length_at_t(t, SPLIT_COUNT))
+    };
+
+    for (i, ca) in curves.iter().enumerate() {
+        if let Curve::Bezier3(c) = ca.1 {
+            if let Some((t_a, t_b)) = c.get_self_intersection_t() {
+                let (index, length_a) = measure(ca, t_a);
+                push(Intersection {
+                    point: c.lerp(t_a),
+                    index_a: index,
+                    index_b: index,
+                    t_a,
+                    t_b,
+                    length_a,
+                    length_b: measure(ca, t_b).1,
+                });
+            }
+        }
+
+        for (j, cb) in curves.iter().enumerate().skip(i + 1) {
+            let is_adjacent = adjacent.contains(&(i, j));
+            for (t_a, t_b) in intersect_curves(&ca.1, &cb.1) {
+                // The shared vertex isn't a crossing
+                if is_adjacent && (t_a == 0.0 || t_a == 1.0) && (t_b == 0.0 || t_b == 1.0) {
+                    continue;
+                }
+                let (index_a, length_a) = measure(ca, t_a);
+                let (index_b, length_b) = measure(cb, t_b);
+                push(Intersection {
+                    point: ca.1.lerp(t_a),
+                    index_a,
+                    index_b,
+                    t_a,
+                    t_b,
+                    length_a,
+                    length_b,
+                });
+            }
+        }
+    }
+
+    ret.sort_by(|x, y| x.length_a.partial_cmp(&y.length_a).unwrap());
+    ret
+}
+
+// Curves with their segment indices and distances from the path start
+pub(crate) fn get_measured_curves(segments: &[PathSegment]) -> Vec<(usize, Curve, f64)> {
+    let mut offset = 0.0;
+    let mut ret = vec![];
+    for subpath in get_subpaths(segments) {
+        for (index, curve) in subpath.curves {
+            ret.push((index, curve, offset));
+            offset += curve.get_appro_length(SPLIT_COUNT);
+        }
+    }
+    ret
+}
+
+// Parameter pairs where the curves cross
+pub(crate) fn intersect_curves(a: &Curve, b: &Curve) -> Vec<(f64, f64)> {
+    match (a, b) {
+        (Curve::Line(la), _) => intersect_line(la, b),
+        (_, Curve::Line(lb)) => intersect_line(lb, a)
+            .into_iter()
+            .map(|(t_b, t_a)| (t_a, t_b))
+            .collect(),
+        _ => intersect_by_subdivision(a, b),
+    }
+}
+
+const EPSILON: f64 = 1e-9;
+
+fn in_unit(t: f64) -> Option<f64> {
+    if (-EPSILON..=1.0 + EPSILON).contains(&t) {
+        Some(t.clamp(0.0, 1.0))
+    } else {
+        None
+    }
+}
+
+// Solves the curve against the implicit form of the line
+fn intersect_line(line: &Line, curve: &Curve) -> Vec<(f64, f64)> {
+    let p0 = line.p0();
+    let d = line.p1() - p0;
+    let dd = d.dot(d);
+    if dd == 0.0 {
+        return vec![];
+    }
+    let n = Vector2(-d.1, d.0);
+
+    let ts: Vec<f64> = match curve {
+        Curve::Line(l) => {
+            let e = l.p1() - l.p0();
+            let denom = n.dot(e);
+            if denom == 0.0 {
+                // Parallel or overlapping
+                return vec![];
+            }
+            in_unit(n.dot(p0 - l.p0()) / denom).into_iter().collect()
+        }
+        Curve::Bezier2(c) => solve_line_polynomial(&c.get_power_coeffs(), n, p0),
+        Curve::Bezier3(c) => solve_line_polynomial(&c.get_power_coeffs(), n, p0),
+        Curve::Arc(c) => solve_line_arc(c, n, p0),
+    };
+
+    ts.into_iter()
+        .filter_map(|t| {
+            let s = (curve.lerp(t) - p0).dot(d) / dd;
+            in_unit(s).map(|s| (s, t))
+        })
+        .collect()
+}
+
+fn solve_line_polynomial(coeffs: &[Vector2], n: Vector2, p0: Vector2) -> Vec<f64> {
+    let mut values: Vec<f64> = coeffs.iter().map(|c| c.dot(n)).collect();
+    values[0] -= n.dot(p0);
+    solve_polynomial(&values, 0.0, 1.0)
+}
+
+// n . (c + U cos(r) + V sin(r) - p0) = 0
+fn solve_line_arc(arc: &Arc, n: Vector2, p0: Vector2) -> Vec<f64> {
+    let u = Vector2(arc.rx(), 0.0).rotate(arc.rotation());
+    let v = Vector2(0.0, arc.ry()).rotate(arc.rotation());
+    let alpha = n.dot(u);
+    let beta = n.dot(v);
+    let gamma = n.dot(arc.center() - p0);
+    let r = alpha.hypot(beta);
+    if r == 0.0 || gamma.abs() > r {
+        return vec![];
+    }
+
+    let phi = beta.atan2(alpha);
+    let delta = (-gamma / r).clamp(-1.0, 1.0).acos();
+    let (lo, hi) = if arc.dtheta() > 0.0 {
+        (arc.theta(), arc.theta() + arc.dtheta())
+    } else {
+        (arc.theta() + arc.dtheta(), arc.theta())
+    };
+
+    let mut ret = vec![];
+    for base in [phi + delta, phi - delta] {
+        let mut r = base + ((lo - base) / (2.0 * PI)).ceil() * 2.0 * PI;
+        // Keeps roots slightly out of the range because of rounding
+        if r - 2.0 * PI >= lo - EPSILON {
+            r -= 2.0 * PI;
+        }
+        while r <= hi + EPSILON {
+            if let Some(t) = in_unit((r - arc.theta()) / arc.dtheta()) {
+                ret.push(t);
+            }
+            r += 2.0 * PI;
+        }
+    }
+    ret.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    ret.dedup_by(|a, b| (*a - *b).abs() < EPSILON);
+    ret
+}
+
+// Subdivides both curves while their bounding boxes overlap. Once both parts
+// are flat, their chords give a starting point for Newton's method.
+fn intersect_by_subdivision(a: &Curve, b: &Curve) -> Vec<(f64, f64)> {
+    let (min_a, max_a) = a.get_bounds();
+    let (min_b, max_b) = b.get_bounds();
+    let scale = (max_a - min_a)
+        .norm()
+        .max((max_b - min_b).norm())
+        .max(EPSILON);
+    let flat = scale * 1e-4;
+
+    let mut ret: Vec<(f64, f64)> = vec![];
+    let mut stack = vec![(0.0, 1.0, 0.0, 1.0, 0)];
+    while let Some((s0, s1, t0, t1, depth)) = stack.pop() {
+        let sa = a.subcurve(s0, s1);
+        let sb = b.subcurve(t0, t1);
+        if !is_overlapping(sa.get_bounds(), sb.get_bounds()) {
+            continue;
+        }
+
+        if depth >= 40 || (sa.is_flat(flat) && sb.is_flat(flat)) {
+            let chord_a = Line::new(sa.start(), sa.end());
+            let chord_b = Curve::Line(Line::new(sb.start(), sb.end()));
+            for (u, v) in intersect_line(&chord_a, &chord_b) {
+                let guess = (s0 + (s1 - s0) * u, t0 + (t1 - t0) * v);
+                if let Some(found) = refine(a, b, guess, scale) {
+                    let duplicated = ret
+                        .iter()
+                        .any(|r| (r.0 - found.0).abs() < 1e-7 && (r.1 - found.1).abs() < 1e-7);
+                    if !duplicated {
+                        ret.push(found);
+                    }
+                }
+            }
+            continue;
+        }
+
+        let sm = (s0 + s1) / 2.0;
+        let tm = (t0 + t1) / 2.0;
+        stack.push((s0, sm, t0, tm, depth + 1));
+        stack.push((s0, sm, tm, t1, depth + 1));
+        stack.push((sm, s1, t0, tm, depth + 1));
+        stack.push((sm, s1, tm, t1, depth + 1));
+    }
+
+    ret.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+    ret
+}
+
+fn is_overlapping(a: (Vector2, Vector2), b: (Vector2, Vector2)) -> bool {
+    a.0 .0 <= b.1 .0 && b.0 .0 <= a.1 .0 && a.0 .1 <= b.1 .1 && b.0 .1 <= a.1 .1
+}
+
+// Newton's method on a(s) - b(t) = 0
+fn refine(a: &Curve, b: &Curve, guess: (f64, f64), scale: f64) -> Option<(f64, f64)> {
+    let (mut s, mut t) = guess;
+    for _ in 0..32 {
+        let f = a.lerp(s) - b.lerp(t);
+        if f.norm() <= scale * 1e-12 {
+            break;
+        }
+        let da = a.derivative(s);
+        let db = b.derivative(t);
+        let det = db.cross(da);
+        if det == 0.0 {
+            break;
+        }
+        // Solves da * ds - db * dt = -f
+        s = (s - db.cross(f) / det).clamp(0.0, 1.0);
+        t = (t - da.cross(f) / det).clamp(0.0, 1.0);
+    }
+
+    let da = a.derivative(s);
+    let db = b.derivative(t);
+    let transversal = da.cross(db).abs() > 1e-9 * da.norm() * db.norm();
+    if transversal && (a.lerp(s) - b.lerp(t)).norm() <= scale * 1e-9 {
+        Some((s, t))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::test_utils::assert_close;
+
+    fn find(a: &str, b: &str) -> Vec<Intersection> {
+        get_intersections(&parse(a), &parse(b))
+    }
+
+    #[test]
+    fn get_intersections_lines() {
+        let ret = find("M0 0 L10 10", "M0 10 L10 0");
+        assert_eq!(
+            ret,
+            vec![Intersection {
+                point: Vector2(5.0, 5.0),
+                index_a: 1,
+                index_b: 1,
+                t_a: 0.5,
+                t_b: 0.5,
+                length_a: 50.0_f64.sqrt(),
+                length_b: 50.0_f64.sqrt(),
+            }]
+        );
+
+        assert_eq!(find("M0 0 L10 0", "M0 1 L10 1"), vec![]);
+        assert_eq!(find("M0 0 L10 0", "M0 0 L10 0"), vec![]);
+        assert_eq!(find("M0 0 L10 0", "M20 -5 L20 5"), vec![]);
+    }
+
+    #[test]
+    fn get_intersections_at_vertices() {
+        let ret = find("M0 0 L5 5 L10 0", "M5 0 L5 10");
+        assert_eq!(ret.len(), 1);
+        assert_close(ret[0].point, Vector2(5.0, 5.0));
+
+        let ret = find("M0 0 h10 v10 h-10 z", "M-5 5 h20");
+        assert_eq!(ret.len(), 2);
+        assert_close(ret[0].point, Vector2(10.0, 5.0));
+        assert_eq!(ret[0].index_a, 2);
+        assert_close(ret[1].point, Vector2(0.0, 5.0));
+        assert_eq!(ret[1].index_a, 4);
+        assert_eq!(ret[1].length_a, 35.0);
+    }
+
+    #[test]
+    fn get_intersections_line_curves() {
+        let ret = find("M0 0 Q5 10 10 0", "M0 2.5 L10 2.5");
+        assert_eq!(ret.len(), 2);
+        for r in &ret {
+            assert_close(r.point, Vector2(r.point.0, 2.5));
+        }
+        assert_close(
+            ret[0].point,
+            Vector2(10.0 * (0.5 - 0.5_f64.sqrt() / 2.0), 2.5),
+        );
+
+        let ret = find("M0 0 C0 10 10 10 10 0", "M5 -5 L5 20");
+        assert_eq!(ret.len(), 1);
+        assert_close(ret[0].point, Vector2(5.0, 7.5));
+
+        let circle = "M10 0 A10 10 0 0 1 -10 0 A10 10 0 0 1 10 0";
+        let ret = find(circle, "M-20 0 L20 0");
+        assert_eq!(ret.len(), 2);
+        assert_close(ret[0].point, Vector2(10.0, 0.0));
+        assert_close(ret[1].point, Vector2(-10.0, 0.0));
+
+        let ret = find("M-20 6 L20 6", circle);
+        assert_eq!(ret.len(), 2);
+        assert_close(ret[0].point, Vector2(-8.0, 6.0));
+        assert_close(ret[1].point, Vector2(8.0, 6.0));
+    }
+
+    #[test]
+    fn get_self_intersections_cases() {
+        let ret = get_self_intersections(&parse("M0 0 L10 10 L10 0 L0 10 Z"));
+        assert_eq!(ret.len(), 1);
+        assert_close(ret[0].point, Vector2(5.0, 5.0));
+        assert_eq!((ret[0].index_a, ret[0].index_b), (1, 3));
+        assert_eq!((ret[0].t_a, ret[0].t_b), (0.5, 0.5));
+        assert!(ret[0].length_a < ret[0].length_b);
+
+        // Vertices aren't crossings
+        assert_eq!(
+            get_self_intersections(&parse("M0 0 h10 v10 h-10 z")),
+            vec![]
+        );
+        assert_eq!(get_self_intersections(&parse("M0 0 L10 0 L10 10")), vec![]);
+
+        // Between subpaths
+        let ret = get_self_intersections(&parse("M0 0 h10 v10 h-10 z M5 5 h10 v10 h-10 z"));
+        assert_eq!(ret.len(), 2);
+        assert_close(ret[0].point, Vector2(10.0, 5.0));
+        assert_close(ret[1].point, Vector2(5.0, 10.0));
+
+        // A loop in a single cubic
+        let ret = get_self_intersections(&parse("M0 0 C20 10 -10 10 10 0"));
+        assert_eq!(ret.len(), 1);
+        assert_eq!((ret[0].index_a, ret[0].index_b), (1, 1));
+        assert!(ret[0].t_a < ret[0].t_b);
+
+        // Adjacent curves crossing away from their vertex
+        let ret = get_self_intersections(&parse("M0 0 Q20 10 0 10 Q-10 0 10 0"));
+        assert_eq!(ret.len(), 1);
+        assert_eq!((ret[0].index_a, ret[0].index_b), (1, 2));
+    }
+
+    #[test]
+    fn get_intersections_curves() {
+        let ret = find("M0 0 C0 10 10 10 10 0", "M0 10 C0 0 10 0 10 10");
+        assert_eq!(ret.len(), 2);
+        for r in &ret {
+            assert_close(r.point, Vector2(r.point.0, 5.0));
+        }
+
+        let ret = find(
+            "M10 0 A10 10 0 0 1 -10 0 A10 10 0 0 1 10 0",
+            "M0 -5 Q40 5 0 5",
+        );
+        assert_eq!(ret.len(), 2);
+        for r in &ret {
+            assert!((r.point.norm() - 10.0).abs() < 1e-7);
+        }
+
+        // Coincident curves don't cross
+        assert_eq!(
+            find("M0 0 C0 10 10 10 10 0", "M0 0 C0 10 10 10 10 0"),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,43 @@
+// Enums spelled as keywords in the arguments
+pub trait Keyword: Copy + 'static {
+    const KEYWORDS: &'static [(&'static str, Self)];
+
+    fn parse(s: &str) -> Option<Self> {
+        Self::KEYWORDS
+            .iter()
+            .find(|(k, _)| *k == s)
+            .map(|(_, v)| *v)
+    }
+}
+
+// Keywords that are values of SVG properties
+pub trait Property: Keyword {
+    // The initial value of the property
+    const DEFAULT: Self;
+
+    // Unknown keywords fall back to the default, as invalid property values
+    // do in SVG
+    fn parse_or_default(s: &str) -> Self {
+        Self::parse(s).unwrap_or(Self::DEFAULT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boolean::BooleanOp;
+    use crate::fill::FillRule;
+
+    #[test]
+    fn parse_or_default_cases() {
+        assert_eq!(FillRule::parse_or_default("evenodd"), FillRule::EvenOdd);
+        assert_eq!(FillRule::parse_or_default("EvenOdd"), FillRule::NonZero);
+        assert_eq!(FillRule::parse_or_default(""), FillRule::NonZero);
+    }
+
+    #[test]
+    fn parse_cases() {
+        assert_eq!(BooleanOp::parse("xor"), Some(BooleanOp::Xor));
+        assert_eq!(BooleanOp::parse("intersect"), None);
+    }
+}
@@ -1,8 +1,33 @@
+pub mod area;
+pub mod boolean;
+pub mod builder;
+pub mod fill;
+pub mod fit;
+pub mod flatten;
+pub mod geometry;
+pub mod intersection;
+pub mod keyword;
+pub mod morph;
+pub mod offset;
+pub mod outline;
 pub mod parser;
 pub mod path;
+pub mod sample;
+pub mod segment;
+pub mod serializer;
+pub mod shapes;
+pub mod simplify;
+pub mod solver;
+pub mod stroke;
+pub mod svg;
+#[cfg(test)]
+mod test_utils;
+pub mod trim;
 pub mod utils;
 pub mod vector;
 
+use keyword::{Keyword, Property};
+use parser::ParseError;
 use wasm_bindgen::prelude::*;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
@@ -16,14 +41,571 @@ extern "C" {}
 
 #[wasm_bindgen(js_name = getTotalLength)]
 pub fn get_total_length(d: &str) -> f64 {
-    path::get_path_length(&parser::parse(d))
+    path::get_data_length(d).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// Returns `{ lengths, errors }` measuring every path at once. `errors` is an
+// `Uint8Array` of the codes below, and the lengths of invalid paths are NaN.
+// 0: none, 1: unexpected token, 2: lack of parameter, 3: unexpected parameter
+#[wasm_bindgen(js_name = getTotalLengths)]
+pub fn get_total_lengths(ds: Vec<String>) -> js_sys::Object {
+    let ds: Vec<&str> = ds.iter().map(|d| d.as_str()).collect();
+    to_lengths_object(&path::get_path_lengths(&ds))
+}
+
+// Same as `getTotalLengths` for the paths joined by the delimiter
+#[wasm_bindgen(js_name = getTotalLengthsDelimited)]
+pub fn get_total_lengths_delimited(ds: &str, delimiter: &str) -> js_sys::Object {
+    let ds: Vec<&str> = if delimiter.is_empty() {
+        vec![ds]
+    } else {
+        ds.split(delimiter).collect()
+    };
+    to_lengths_object(&path::get_path_lengths(&ds))
+}
+
+fn to_lengths_object(items: &[Result<f64, ParseError>]) -> js_sys::Object {
+    let lengths: Vec<f64> = items
+        .iter()
+        .map(|r| *r.as_ref().unwrap_or(&f64::NAN))
+        .collect();
+    let errors: Vec<u8> = items
+        .iter()
+        .map(|r| r.as_ref().err().map_or(0, get_error_code))
+        .collect();
+    let obj = js_sys::Object::new();
+    set_property(
+        &obj,
+        "lengths",
+        js_sys::Float64Array::from(&lengths[..]).into(),
+    );
+    set_property(&obj, "errors", js_sys::Uint8Array::from(&errors[..]).into());
+    obj
+}
+
+fn get_error_code(error: &ParseError) -> u8 {
+    match error {
+        ParseError::UnexpectedToken(_) | ParseError::UnexpectedCommand(_) => 1,
+        ParseError::LackOfParameter(_) => 2,
+        ParseError::UnexpectedParameter(_) => 3,
+    }
 }
 
 #[wasm_bindgen(js_name = getPointAtLength)]
+#[allow(unused_variables)]
 pub fn get_point_at_length(d: &str, distance: f64) -> js_sys::Float64Array {
     js_sys::Float64Array::from(&vec![0.0, 0.0][..])
 }
 
+// Returns `[x, y, distance, index, t, length]`, or an empty array when the
+// path draws nothing
+#[wasm_bindgen(js_name = getClosestPoint)]
+pub fn get_closest_point(d: &str, x: f64, y: f64) -> js_sys::Float64Array {
+    match path::get_closest_point(&parser::parse(d), vector::Vector2(x, y)) {
+        Some(c) => js_sys::Float64Array::from(
+            &[
+                c.point.0,
+                c.point.1,
+                c.distance,
+                c.index as f64,
+                c.t,
+                c.length,
+            ][..],
+        ),
+        None => js_sys::Float64Array::new_with_length(0),
+    }
+}
+
+#[wasm_bindgen(js_name = getSubpath)]
+pub fn get_subpath(d: &str, from: f64, to: f64) -> String {
+    serializer::serialize(&trim::get_subpath(&parser::parse(d), from, to))
+}
+
+// Each dash becomes a subpath. The path comes back as it is when the array
+// would render solid, or when it'd take more than 2^20 dashes.
+#[wasm_bindgen]
+pub fn dash(d: &str, dasharray: &[f64], dashoffset: f64) -> String {
+    serializer::serialize(&trim::get_dashes(&parser::parse(d), dasharray, dashoffset))
+}
+
+// Returns `[n, closed, x0, y0, ..., xn-1, yn-1]` for each subpath, where
+// `closed` is 1 or 0 and closed polylines don't repeat their start point.
+// The array is empty unless `tolerance` is positive.
+#[wasm_bindgen(js_name = flatten)]
+pub fn flatten_path(d: &str, tolerance: f64) -> js_sys::Float64Array {
+    let mut values: Vec<f64> = vec![];
+    for p in flatten::flatten(&parser::parse(d), tolerance) {
+        values.push(p.points.len() as f64);
+        values.push(if p.closed { 1.0 } else { 0.0 });
+        values.extend(p.points.iter().flat_map(|v| [v.0, v.1]));
+    }
+    js_sys::Float64Array::from(&values[..])
+}
+
+// Fits cubic curves within `error` to `[x0, y0, x1, y1, ...]`, splitting them
+// where the points turn more than `corner_angle` in radians
+#[wasm_bindgen(js_name = fitCurves)]
+pub fn fit_curves(points: &[f64], error: f64, corner_angle: f64) -> String {
+    serializer::serialize(&fit::fit_curves(&to_points(points), error, corner_angle))
+}
+
+// Blends two paths by `t` in [0, 1]
+#[wasm_bindgen]
+pub fn interpolate(a: &str, b: &str, t: f64) -> String {
+    serializer::serialize(&morph::interpolate(&parser::parse(a), &parser::parse(b), t))
+}
+
+// Two paths prepared for blending them every frame
+#[wasm_bindgen]
+pub struct Morph(morph::Morph);
+
+#[wasm_bindgen]
+impl Morph {
+    #[wasm_bindgen(constructor)]
+    pub fn new(a: &str, b: &str) -> Morph {
+        Morph(morph::Morph::new(&parser::parse(a), &parser::parse(b)))
+    }
+
+    pub fn at(&self, t: f64) -> String {
+        serializer::serialize(&self.0.at(t))
+    }
+}
+
+// Equivalent paths of the SVG basic shapes. Missing radii are "auto".
+#[wasm_bindgen(js_name = rectToPath)]
+pub fn rect_to_path(
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    rx: Option<f64>,
+    ry: Option<f64>,
+) -> String {
+    serializer::serialize(&shapes::rect(x, y, width, height, rx, ry))
+}
+
+#[wasm_bindgen(js_name = circleToPath)]
+pub fn circle_to_path(cx: f64, cy: f64, r: f64) -> String {
+    serializer::serialize(&shapes::circle(cx, cy, r))
+}
+
+#[wasm_bindgen(js_name = ellipseToPath)]
+pub fn ellipse_to_path(cx: f64, cy: f64, rx: Option<f64>, ry: Option<f64>) -> String {
+    serializer::serialize(&shapes::ellipse(cx, cy, rx, ry))
+}
+
+#[wasm_bindgen(js_name = lineToPath)]
+pub fn line_to_path(x1: f64, y1: f64, x2: f64, y2: f64) -> String {
+    serializer::serialize(&shapes::line(x1, y1, x2, y2))
+}
+
+// `points` is `[x0, y0, x1, y1, ...]`
+#[wasm_bindgen(js_name = polylineToPath)]
+pub fn polyline_to_path(points: &[f64]) -> String {
+    serializer::serialize(&shapes::polyline(&to_points(points)))
+}
+
+#[wasm_bindgen(js_name = polygonToPath)]
+pub fn polygon_to_path(points: &[f64]) -> String {
+    serializer::serialize(&shapes::polygon(&to_points(points)))
+}
+
+fn to_points(values: &[f64]) -> Vec<vector::Vector2> {
+    values
+        .chunks_exact(2)
+        .map(|c| vector::Vector2(c[0], c[1]))
+        .collect()
+}
+
+// Built from JS step by step as `builder.moveTo(0, 0)`, then read with
+// `builder.toString()`
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct PathBuilder(builder::PathBuilder);
+
+#[wasm_bindgen]
+impl PathBuilder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PathBuilder {
+        PathBuilder::default()
+    }
+
+    #[wasm_bindgen(js_name = moveTo)]
+    pub fn move_to(&mut self, x: f64, y: f64) {
+        self.0.move_to(x, y);
+    }
+
+    #[wasm_bindgen(js_name = relMoveTo)]
+    pub fn rel_move_to(&mut self, dx: f64, dy: f64) {
+        self.0.rel_move_to(dx, dy);
+    }
+
+    #[wasm_bindgen(js_name = lineTo)]
+    pub fn line_to(&mut self, x: f64, y: f64) {
+        self.0.line_to(x, y);
+    }
+
+    #[wasm_bindgen(js_name = relLineTo)]
+    pub fn rel_line_to(&mut self, dx: f64, dy: f64) {
+        self.0.rel_line_to(dx, dy);
+    }
+
+    pub fn h(&mut self, x: f64) {
+        self.0.h(x);
+    }
+
+    #[wasm_bindgen(js_name = relH)]
+    pub fn rel_h(&mut self, dx: f64) {
+        self.0.rel_h(dx);
+    }
+
+    pub fn v(&mut self, y: f64) {
+        self.0.v(y);
+    }
+
+    #[wasm_bindgen(js_name = relV)]
+    pub fn rel_v(&mut self, dy: f64) {
+        self.0.rel_v(dy);
+    }
+
+    #[wasm_bindgen(js_name = quadTo)]
+    pub fn quad_to(&mut self, x1: f64, y1: f64, x: f64, y: f64) {
+        self.0.quad_to(x1, y1, x, y);
+    }
+
+    #[wasm_bindgen(js_name = relQuadTo)]
+    pub fn rel_quad_to(&mut self, dx1: f64, dy1: f64, dx: f64, dy: f64) {
+        self.0.rel_quad_to(dx1, dy1, dx, dy);
+    }
+
+    #[wasm_bindgen(js_name = smoothQuadTo)]
+    pub fn smooth_quad_to(&mut self, x: f64, y: f64) {
+        self.0.smooth_quad_to(x, y);
+    }
+
+    #[wasm_bindgen(js_name = relSmoothQuadTo)]
+    pub fn rel_smooth_quad_to(&mut self, dx: f64, dy: f64) {
+        self.0.rel_smooth_quad_to(dx, dy);
+    }
+
+    #[wasm_bindgen(js_name = cubicTo)]
+    pub fn cubic_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x: f64, y: f64) {
+        self.0.cubic_to(x1, y1, x2, y2, x, y);
+    }
+
+    #[wasm_bindgen(js_name = relCubicTo)]
+    pub fn rel_cubic_to(&mut self, dx1: f64, dy1: f64, dx2: f64, dy2: f64, dx: f64, dy: f64) {
+        self.0.rel_cubic_to(dx1, dy1, dx2, dy2, dx, dy);
+    }
+
+    #[wasm_bindgen(js_name = smoothCubicTo)]
+    pub fn smooth_cubic_to(&mut self, x2: f64, y2: f64, x: f64, y: f64) {
+        self.0.smooth_cubic_to(x2, y2, x, y);
+    }
+
+    #[wasm_bindgen(js_name = relSmoothCubicTo)]
+    pub fn rel_smooth_cubic_to(&mut self, dx2: f64, dy2: f64, dx: f64, dy: f64) {
+        self.0.rel_smooth_cubic_to(dx2, dy2, dx, dy);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[wasm_bindgen(js_name = arcTo)]
+    pub fn arc_to(
+        &mut self,
+        rx: f64,
+        ry: f64,
+        rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        x: f64,
+        y: f64,
+    ) {
+        self.0.arc_to(rx, ry, rotation, large_arc, sweep, x, y);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[wasm_bindgen(js_name = relArcTo)]
+    pub fn rel_arc_to(
+        &mut self,
+        rx: f64,
+        ry: f64,
+        rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        dx: f64,
+        dy: f64,
+    ) {
+        self.0
+            .rel_arc_to(rx, ry, rotation, large_arc, sweep, dx, dy);
+    }
+
+    pub fn close(&mut self) {
+        self.0.close();
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn serialize(&self) -> String {
+        self.0.serialize()
+    }
+}
+
+// Returns `[x, y]`, or `[x, y, angle]` with `tangents`, for each of `count`
+// points at equal distances. `jumps` makes the gaps between subpaths count
+// as lines.
+#[wasm_bindgen(js_name = samplePoints)]
+pub fn sample_points(d: &str, count: usize, tangents: bool, jumps: bool) -> js_sys::Float64Array {
+    to_sample_array(
+        &sample::sample_points(&parser::parse(d), count, jumps),
+        tangents,
+    )
+}
+
+// Same layout as `samplePoints` for points every `spacing`, empty when it'd
+// take more than 2^20 points
+#[wasm_bindgen(js_name = samplePointsEvery)]
+pub fn sample_points_every(
+    d: &str,
+    spacing: f64,
+    tangents: bool,
+    jumps: bool,
+) -> js_sys::Float64Array {
+    to_sample_array(
+        &sample::sample_points_every(&parser::parse(d), spacing, jumps),
+        tangents,
+    )
+}
+
+// Simplifies runs of straight lines within `tolerance` by `method`, "rdp"
+// or "visvalingam". Throws for other methods.
+#[wasm_bindgen]
+pub fn simplify(d: &str, tolerance: f64, method: &str) -> Result<String, String> {
+    let method = simplify::SimplifyMethod::parse(method)
+        .ok_or_else(|| format!("Unknown simplify method: {}", method))?;
+    Ok(serializer::serialize(&simplify::simplify(
+        &parser::parse(d),
+        tolerance,
+        method,
+    )))
+}
+
+fn to_sample_array(samples: &[sample::Sample], tangents: bool) -> js_sys::Float64Array {
+    let mut values: Vec<f64> = vec![];
+    for s in samples {
+        values.extend([s.point.0, s.point.1]);
+        if tangents {
+            values.push(s.angle);
+        }
+    }
+    js_sys::Float64Array::from(&values[..])
+}
+
+#[wasm_bindgen(js_name = isPointInFill)]
+pub fn is_point_in_fill(d: &str, x: f64, y: f64, fill_rule: &str) -> bool {
+    fill::is_point_in_fill(
+        &parser::parse(d),
+        vector::Vector2(x, y),
+        fill::FillRule::parse_or_default(fill_rule),
+    )
+}
+
+#[wasm_bindgen(js_name = isPointInStroke)]
+pub fn is_point_in_stroke(
+    d: &str,
+    x: f64,
+    y: f64,
+    stroke_width: f64,
+    line_join: &str,
+    line_cap: &str,
+    miter_limit: f64,
+) -> bool {
+    stroke::is_point_in_stroke(
+        &parser::parse(d),
+        vector::Vector2(x, y),
+        &stroke::StrokeStyle {
+            width: stroke_width,
+            join: stroke::LineJoin::parse_or_default(line_join),
+            cap: stroke::LineCap::parse_or_default(line_cap),
+            miter_limit,
+        },
+    )
+}
+
+// Outline to be filled with the nonzero rule
+#[wasm_bindgen(js_name = strokeToPath)]
+pub fn stroke_to_path(
+    d: &str,
+    stroke_width: f64,
+    line_join: &str,
+    line_cap: &str,
+    miter_limit: f64,
+    tolerance: f64,
+) -> String {
+    serializer::serialize(&stroke::get_stroke_outline(
+        &parser::parse(d),
+        &stroke::StrokeStyle {
+            width: stroke_width,
+            join: stroke::LineJoin::parse_or_default(line_join),
+            cap: stroke::LineCap::parse_or_default(line_cap),
+            miter_limit,
+        },
+        tolerance,
+    ))
+}
+
+// Grows the fill by `distance`, or shrinks it when negative. Curves are
+// offset within a thousandth of the distance, and miters use the initial
+// miter limit.
+#[wasm_bindgen]
+pub fn offset(d: &str, distance: f64, join_style: &str) -> String {
+    let default = stroke::StrokeStyle::default();
+    serializer::serialize(&offset::get_offset(
+        &parser::parse(d),
+        distance,
+        stroke::LineJoin::parse_or_default(join_style),
+        default.miter_limit,
+        (distance.abs() * 1e-3).max(1e-9),
+    ))
+}
+
+// Sum of the signed areas of the subpaths, positive when clockwise on screen
+#[wasm_bindgen(js_name = getArea)]
+pub fn get_area(d: &str) -> f64 {
+    area::get_area(&parser::parse(d))
+}
+
+// Returns `[x, y]`, or an empty array when the area is zero
+#[wasm_bindgen(js_name = getCentroid)]
+pub fn get_centroid(d: &str) -> js_sys::Float64Array {
+    match area::get_centroid(&parser::parse(d)) {
+        Some(c) => js_sys::Float64Array::from(&[c.0, c.1][..]),
+        None => js_sys::Float64Array::new_with_length(0),
+    }
+}
+
+// Returns `[area, x, y]` for each subpath, where the centroid is NaN when the
+// area is zero. The sign of the area tells the orientation.
+#[wasm_bindgen(js_name = getSubpathAreas)]
+pub fn get_subpath_areas(d: &str) -> js_sys::Float64Array {
+    let values: Vec<f64> = area::get_subpath_areas(&parser::parse(d))
+        .iter()
+        .flat_map(|s| {
+            let c = s.centroid.unwrap_or(vector::Vector2(f64::NAN, f64::NAN));
+            [s.area, c.0, c.1]
+        })
+        .collect();
+    js_sys::Float64Array::from(&values[..])
+}
+
+// Throws for operations other than "union", "intersection", "difference"
+// and "xor"
+#[wasm_bindgen(js_name = booleanOp)]
+pub fn boolean_op(
+    a: &str,
+    b: &str,
+    op: &str,
+    fill_rule_a: &str,
+    fill_rule_b: &str,
+) -> Result<String, String> {
+    let op = boolean::BooleanOp::parse(op)
+        .ok_or_else(|| format!("Unknown boolean operation: {}", op))?;
+    let a = parser::parse(a);
+    let b = parser::parse(b);
+    Ok(serializer::serialize(&boolean::get_boolean(
+        boolean::Fill {
+            segments: &a,
+            rule: fill::FillRule::parse_or_default(fill_rule_a),
+        },
+        boolean::Fill {
+            segments: &b,
+            rule: fill::FillRule::parse_or_default(fill_rule_b),
+        },
+        op,
+    )))
+}
+
+// Returns `[x, y, index_a, index_b, t_a, t_b, length_a, length_b]` for each
+// crossing, sorted along `a`
+#[wasm_bindgen]
+pub fn intersections(a: &str, b: &str) -> js_sys::Float64Array {
+    to_intersection_array(&intersection::get_intersections(
+        &parser::parse(a),
+        &parser::parse(b),
+    ))
+}
+
+// Same layout as `intersections`, where `a` and `b` are both the path
+#[wasm_bindgen(js_name = selfIntersections)]
+pub fn self_intersections(d: &str) -> js_sys::Float64Array {
+    to_intersection_array(&intersection::get_self_intersections(&parser::parse(d)))
+}
+
+fn to_intersection_array(items: &[intersection::Intersection]) -> js_sys::Float64Array {
+    let values: Vec<f64> = items
+        .iter()
+        .flat_map(|i| {
+            [
+                i.point.0,
+                i.point.1,
+                i.index_a as f64,
+                i.index_b as f64,
+                i.t_a,
+                i.t_b,
+                i.length_a,
+                i.length_b,
+            ]
+        })
+        .collect();
+    js_sys::Float64Array::from(&values[..])
+}
+
+// Each item is `{ d, x, y, closed, length, begin, end }`, where `x` and `y`
+// are the start point and `begin..end` is the segment range in `d`
+#[wasm_bindgen(js_name = splitSubpaths)]
+pub fn split_subpaths(d: &str) -> js_sys::Array {
+    path::split_subpaths(&parser::parse(d))
+        .iter()
+        .map(|s| {
+            let obj = js_sys::Object::new();
+            set_property(&obj, "d", serializer::serialize(&s.segments).into());
+            set_property(&obj, "x", s.start.0.into());
+            set_property(&obj, "y", s.start.1.into());
+            set_property(&obj, "closed", s.closed.into());
+            set_property(&obj, "length", s.length.into());
+            set_property(&obj, "begin", s.range.start.into());
+            set_property(&obj, "end", s.range.end.into());
+            JsValue::from(obj)
+        })
+        .collect()
+}
+
+// Each item is `{ id, tag, d, length }` for every path and basic shape in
+// the SVG document, where `d` has the transforms of its ancestors applied,
+// `length` is measured before them as `getTotalLength` does and `id` is
+// undefined when missing
+#[wasm_bindgen(js_name = getSvgPaths)]
+pub fn get_svg_paths(svg: &str) -> js_sys::Array {
+    svg::get_svg_paths(svg)
+        .iter()
+        .map(|p| {
+            let obj = js_sys::Object::new();
+            set_property(
+                &obj,
+                "id",
+                p.id.as_deref()
+                    .map_or(JsValue::UNDEFINED, JsValue::from_str),
+            );
+            set_property(&obj, "tag", JsValue::from_str(&p.tag));
+            set_property(&obj, "d", serializer::serialize(&p.segments).into());
+            set_property(&obj, "length", p.length.into());
+            JsValue::from(obj)
+        })
+        .collect()
+}
+
+fn set_property(obj: &js_sys::Object, key: &str, value: JsValue) {
+    js_sys::Reflect::set(obj, &JsValue::from_str(key), &value).unwrap();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -33,4 +615,108 @@ mod tests {
         assert_eq!(get_total_length("M10 10 L 40 10 L40 50"), 70.0);
         assert_eq!(get_total_length("M10 10 L 40 10 L40 50z"), 120.0);
     }
+
+    #[test]
+    fn get_error_code_cases() {
+        let code = |d: &str| get_error_code(&parser::try_parse(d).unwrap_err());
+        assert_eq!(code("M0 0 K"), 1);
+        assert_eq!(code("M0 0 L1"), 2);
+        assert_eq!(code("M0 0 L1 L"), 3);
+    }
+
+    #[test]
+    fn get_subpath_cases() {
+        assert_eq!(
+            get_subpath("M10 10 L 40 10 L40 50", 10.0, 40.0),
+            "M20 10 L40 10 L40 20"
+        );
+    }
+
+    #[test]
+    fn path_builder_cases() {
+        let mut builder = PathBuilder::new();
+        builder.move_to(0.0, 0.0);
+        builder.rel_line_to(10.0, 0.0);
+        builder.close();
+        assert_eq!(builder.serialize(), "M0 0 l10 0 Z");
+    }
+
+    #[test]
+    fn boolean_op_cases() {
+        let a = "M0 0 h20 v20 h-20 z";
+        let b = "M20 0 h20 v20 h-20 z";
+        assert_eq!(
+            boolean_op(a, b, "union", "nonzero", "nonzero"),
+            Ok("M0 0 L40 0 L40 20 L0 20 Z".to_string())
+        );
+        // Unknown fill rules are nonzero, unknown operations an error
+        assert_eq!(
+            boolean_op(a, b, "union", "", ""),
+            boolean_op(a, b, "union", "nonzero", "nonzero")
+        );
+        assert_eq!(
+            boolean_op(a, b, "intersect", "", ""),
+            Err("Unknown boolean operation: intersect".to_string())
+        );
+        assert_eq!(boolean_op(a, b, "intersection", "", ""), Ok(String::new()));
+    }
+
+    #[test]
+    fn simplify_cases() {
+        let d = "M0 0 l1 0.1 l1 -0.1 l1 0 L10 10";
+        assert_eq!(
+            simplify(d, 0.5, "visvalingam"),
+            Ok("M0 0 L3 0 L10 10".to_string())
+        );
+        assert_eq!(
+            simplify(d, 0.5, "rpd"),
+            Err("Unknown simplify method: rpd".to_string())
+        );
+    }
+
+    #[test]
+    fn stroke_to_path_cases() {
+        assert_eq!(
+            stroke_to_path("M0 0 L10 0", 2.0, "", "square", 4.0, 0.1),
+            "M-1 -1 L11 -1 L11 1 L-1 1 Z"
+        );
+    }
+
+    #[test]
+    fn offset_cases() {
+        assert_eq!(
+            offset("M0 0 h10 v10 h-10 z", -1.0, "bevel"),
+            "M1 1 L9 1 L9 9 L1 9 Z"
+        );
+        assert_eq!(offset("M0 0 h10 v10 h-10 z", -6.0, "round"), "");
+    }
+
+    #[test]
+    fn dash_cases() {
+        assert_eq!(dash("M0 0 L10 0", &[4.0, 2.0], 2.0), "M0 0 L2 0 M4 0 L8 0");
+    }
+
+    #[test]
+    fn is_point_in_fill_cases() {
+        let d = "M0 0 h30 v30 h-30 z M10 10 h10 v10 h-10 z";
+        assert!(is_point_in_fill(d, 15.0, 15.0, "nonzero"));
+        assert!(!is_point_in_fill(d, 15.0, 15.0, "evenodd"));
+        assert!(is_point_in_fill(d, 15.0, 15.0, ""));
+    }
+
+    #[test]
+    fn get_area_cases() {
+        assert_eq!(get_area("M10 10 L 40 10 L40 50z"), 600.0);
+        assert_eq!(get_area("M10 10 L40 50 L 40 10z"), -600.0);
+    }
+
+    #[test]
+    fn is_point_in_stroke_cases() {
+        let d = "M0 0 L10 0 L10 10";
+        assert!(is_point_in_stroke(d, 5.0, 0.5, 2.0, "miter", "butt", 4.0));
+        assert!(is_point_in_stroke(d, 10.9, -0.9, 2.0, "", "", 4.0));
+        assert!(!is_point_in_stroke(
+            d, 10.9, -0.9, 2.0, "round", "butt", 4.0
+        ));
+    }
 }
@@ -0,0 +1,275 @@
+use crate::path::{get_subpaths, PathSegment, SPLIT_COUNT};
+use crate::vector::{lerp, Bezier3, Curve, Lerpable, Vector2};
+
+// A pair of paths prepared for blending. Subpaths are matched by their
+// positions and made of the same number of cubic curves, so that each frame
+// only needs to lerp control points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Morph {
+    pairs: Vec<(MorphSubpath, MorphSubpath)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MorphSubpath {
+    curves: Vec<Bezier3>,
+    closed: bool,
+}
+
+impl Morph {
+    pub fn new(a: &[PathSegment], b: &[PathSegment]) -> Self {
+        let mut a = get_morph_subpaths(a);
+        let mut b = get_morph_subpaths(b);
+
+        // Subpaths without counterparts grow from or shrink into a point
+        let mut pairs: Vec<(MorphSubpath, MorphSubpath)> = vec![];
+        while !a.is_empty() && !b.is_empty() {
+            let c = get_center(&a[0]);
+            let (j, _) = b
+                .iter()
+                .enumerate()
+                .map(|(j, s)| (j, (get_center(s) - c).norm()))
+                .fold(
+                    (0, f64::INFINITY),
+                    |acc, v| if v.1 < acc.1 { v } else { acc },
+                );
+            pairs.push((a.remove(0), b.remove(j)));
+        }
+        for s in a {
+            let p = get_point_subpath(get_center(&s), s.closed);
+            pairs.push((s, p));
+        }
+        for s in b {
+            let p = get_point_subpath(get_center(&s), s.closed);
+            pairs.push((p, s));
+        }
+
+        for (a, b) in pairs.iter_mut() {
+            while a.curves.len() < b.curves.len() {
+                split_longest(&mut a.curves);
+            }
+            while b.curves.len() < a.curves.len() {
+                split_longest(&mut b.curves);
+            }
+            if a.closed && b.closed {
+                rotate_closest(&a.curves, &mut b.curves);
+            }
+        }
+        Self { pairs }
+    }
+
+    // The blended path. Each subpath is closed when the nearer end of the
+    // blend is closed.
+    pub fn at(&self, t: f64) -> Vec<PathSegment> {
+        let mut ret: Vec<PathSegment> = vec![];
+        for (a, b) in &self.pairs {
+            let p = lerp(&a.curves[0].p0(), &b.curves[0].p0(), t);
+            ret.push(PathSegment::new('M', vec![p.0, p.1]));
+            for (ca, cb) in a.curves.iter().zip(&b.curves) {
+                let p1 = lerp(&ca.p1(), &cb.p1(), t);
+                let p2 = lerp(&ca.p2(), &cb.p2(), t);
+                let p3 = lerp(&ca.p3(), &cb.p3(), t);
+                ret.push(PathSegment::new(
+                    'C',
+                    vec![p1.0, p1.1, p2.0, p2.1, p3.0, p3.1],
+                ));
+            }
+            let closed = if t < 0.5 { a.closed } else { b.closed };
+            if closed {
+                ret.push(PathSegment::new('Z', vec![]));
+            }
+        }
+        ret
+    }
+}
+
+// Blends two paths. Use `Morph` to blend the same paths many times.
+pub fn interpolate(a: &[PathSegment], b: &[PathSegment], t: f64) -> Vec<PathSegment> {
+    Morph::new(a, b).at(t)
+}
+
+fn get_morph_subpaths(segments: &[PathSegment]) -> Vec<MorphSubpath> {
+    get_subpaths(segments)
+        .iter()
+        .map(|subpath| {
+            let mut curves: Vec<Bezier3> = vec![];
+            for (_, curve) in &subpath.curves {
+                curves.extend(to_cubics(curve));
+            }
+            // 'Z' right after returning to the start draws nothing
+            if subpath.closed && curves.len() > 1 {
+                let last = curves[curves.len() - 1];
+                if last.p0() == last.p3() {
+                    curves.pop();
+                }
+            }
+            if curves.is_empty() {
+                get_point_subpath(subpath.start, subpath.closed)
+            } else {
+                MorphSubpath {
+                    curves,
+                    closed: subpath.closed,
+                }
+            }
+        })
+        .collect()
+}
+
+fn get_point_subpath(p: Vector2, closed: bool) -> MorphSubpath {
+    MorphSubpath {
+        curves: vec![Bezier3::new(p, p, p, p)],
+        closed,
+    }
+}
+
+// Exact for lines and quadratic curves, arcs are approximated within quarter
+// turns
+pub(crate) fn to_cubics(curve: &Curve) -> Vec<Bezier3> {
+    match curve {
+        Curve::Line(c) => {
+            let d = (c.p1() - c.p0()).multi(1.0 / 3.0);
+            vec![Bezier3::new(c.p0(), c.p0() + d, c.p1() - d, c.p1())]
+        }
+        Curve::Bezier2(c) => vec![Bezier3::new(
+            c.p0(),
+            c.p0() + (c.p1() - c.p0()).multi(2.0 / 3.0),
+            c.p2() + (c.p1() - c.p2()).multi(2.0 / 3.0),
+            c.p2(),
+        )],
+        Curve::Bezier3(c) => vec![*c],
+        Curve::Arc(c) => {
+            let count = (c.dtheta().abs() / (std::f64::consts::PI / 2.0))
+                .ceil()
+                .max(1.0) as usize;
+            (0..count)
+                .map(|i| {
+                    let arc = c.subcurve(i as f64 / count as f64, (i + 1) as f64 / count as f64);
+                    let dtheta = arc.dtheta();
+                    // Derivatives are dtheta times the tangent of the angle
+                    let k = if dtheta == 0.0 {
+                        0.0
+                    } else {
+                        4.0 / 3.0 * (dtheta / 4.0).tan() / dtheta
+                    };
+                    Bezier3::new(
+                        arc.p0(),
+                        arc.p0() + arc.derivative(0.0).multi(k),
+                        arc.p1() - arc.derivative(1.0).multi(k),
+                        arc.p1(),
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+fn get_center(subpath: &MorphSubpath) -> Vector2 {
+    let mut min = subpath.curves[0].p0();
+    let mut max = min;
+    for c in &subpath.curves {
+        let (a, b) = Curve::Bezier3(*c).get_bounds();
+        min = Vector2(min.0.min(a.0), min.1.min(a.1));
+        max = Vector2(max.0.max(b.0), max.1.max(b.1));
+    }
+    (min + max).multi(0.5)
+}
+
+fn split_longest(curves: &mut Vec<Bezier3>) {
+    let (i, _) = curves
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, c.get_appro_length(SPLIT_COUNT)))
+        .fold((0, -1.0), |acc, v| if v.1 > acc.1 { v } else { acc });
+    let (a, b) = curves[i].split_at(0.5);
+    curves[i] = a;
+    curves.insert(i + 1, b);
+}
+
+// Rotates the closed loop `b` to start where the points travel the least
+fn rotate_closest(a: &[Bezier3], b: &mut [Bezier3]) {
+    let travel = |k: usize| -> f64 {
+        a.iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let d = b[(i + k) % b.len()];
+                (c.p0() - d.p0()).norm() + (c.p1() - d.p1()).norm() + (c.p2() - d.p2()).norm()
+            })
+            .sum()
+    };
+    let (k, _) = (0..b.len())
+        .map(|k| (k, travel(k)))
+        .fold(
+            (0, f64::INFINITY),
+            |acc, v| if v.1 < acc.1 { v } else { acc },
+        );
+    b.rotate_left(k);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::get_area;
+    use crate::parser::parse;
+    use crate::serializer::serialize;
+
+    #[test]
+    fn interpolate_cases() {
+        let a = parse("M0 0 L10 0");
+        let b = parse("M0 10 L20 10");
+        assert_eq!(serialize(&interpolate(&a, &b, 0.5)), "M0 5 C5 5 10 5 15 5");
+
+        // Both ends are the shapes themselves
+        let a = parse("M0 0 h10 v10 h-10 z");
+        let b = parse("M5 0 A5 5 0 0 1 5 10 A5 5 0 0 1 5 0 z");
+        let morph = Morph::new(&a, &b);
+        for (t, d) in [(0.0, &a), (1.0, &b)] {
+            let ret = morph.at(t);
+            assert_eq!(ret.len(), 6);
+            assert_eq!(ret[5]._type, 'Z');
+            assert!((get_area(&ret) - get_area(d)).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn interpolate_rotates_start() {
+        // The square starting at the other corner lines up with no travel
+        let a = parse("M0 0 h10 v10 h-10 z");
+        let b = parse("M10 10 h-10 v-10 h10 z");
+        assert_eq!(
+            serialize(&interpolate(&a, &b, 0.5)),
+            serialize(&interpolate(&a, &a, 0.5))
+        );
+    }
+
+    #[test]
+    fn interpolate_subpaths() {
+        // Subpaths are matched by their positions
+        let a = parse("M0 0 h1 M100 0 h1");
+        let b = parse("M100 10 h1 M0 10 h1");
+        let ret = serialize(&interpolate(&a, &b, 0.5));
+        assert!(ret.starts_with("M0 5 C"), "{}", ret);
+
+        // The extra one grows from its center
+        let a = parse("M0 0 h10");
+        let b = parse("M0 0 h10 M20 0 h10");
+        let ret = interpolate(&a, &b, 0.0);
+        assert_eq!(ret.len(), 4);
+        assert_eq!(ret[2].values, vec![25.0, 0.0]);
+        assert_eq!(ret[3].values[4..], [25.0, 0.0]);
+    }
+
+    #[test]
+    fn to_cubics_cases() {
+        let circle = parse("M10 0 A10 10 0 1 1 -10 0 A10 10 0 1 1 10 0");
+        let curves: Vec<Bezier3> = get_subpaths(&circle)[0]
+            .curves
+            .iter()
+            .flat_map(|(_, c)| to_cubics(c))
+            .collect();
+        assert_eq!(curves.len(), 4);
+        for c in curves {
+            for i in 0..=10 {
+                assert!((c.lerp(i as f64 / 10.0).norm() - 10.0).abs() < 0.03);
+            }
+        }
+    }
+}
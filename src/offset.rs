@@ -0,0 +1,131 @@
+use crate::boolean::get_region_outline;
+use crate::fill::{get_closing_curve, get_winding_number, is_point_in_fill, FillRule};
+use crate::outline::{get_normal, get_side, get_tangent};
+use crate::path::{from_closed_curves, get_subpaths, PathSegment};
+use crate::stroke::LineJoin;
+use crate::vector::{Curve, Lerpable, Vector2};
+
+// Grows the nonzero fill of the path by `distance`, or shrinks it when
+// negative. Each subpath is offset on its own, then the loops and
+// overlaps left by concave corners and large insets are removed by keeping
+// only the region where the offset loops wind positively in total. Subpaths
+// with the fill on both sides or on neither don't bound it and are skipped.
+pub fn get_offset(
+    segments: &[PathSegment],
+    distance: f64,
+    join: LineJoin,
+    miter_limit: f64,
+    tolerance: f64,
+) -> Vec<PathSegment> {
+    let mut loops: Vec<PathSegment> = vec![];
+    let mut all: Vec<Curve> = vec![];
+    for subpath in get_subpaths(segments) {
+        let mut curves: Vec<Curve> = subpath.curves.iter().map(|(_, c)| *c).collect();
+        curves.extend(get_closing_curve(&subpath));
+        curves.retain(|c| c.get_start_tangent() != Vector2(0.0, 0.0));
+
+        // Turns the subpath so that the fill is on the side of the normals
+        match get_fill_side(segments, &curves) {
+            Some(true) => {}
+            Some(false) => curves = curves.iter().rev().map(|c| c.reverse()).collect(),
+            None => continue,
+        }
+
+        let side = if distance == 0.0 {
+            curves
+        } else {
+            get_side(&curves, -distance, join, miter_limit, tolerance, true)
+        };
+        loops.extend(from_closed_curves(&side));
+        all.extend(side);
+    }
+
+    get_region_outline(&all, |p| get_winding_number(&loops, p) > 0)
+}
+
+// Whether the fill is on the side of the normals of the curves, looking for
+// a point where it's on one side only
+fn get_fill_side(segments: &[PathSegment], curves: &[Curve]) -> Option<bool> {
+    for c in curves {
+        let (min, max) = c.get_bounds();
+        for t in [0.5, 0.25, 0.75] {
+            let p = c.lerp(t);
+            let n = get_normal(get_tangent(c, t)).multi((max - min).norm() * 1e-6);
+            let right = is_point_in_fill(segments, p + n, FillRule::NonZero);
+            let left = is_point_in_fill(segments, p - n, FillRule::NonZero);
+            if right != left {
+                return Some(right);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::get_area;
+    use crate::parser::parse;
+    use crate::serializer::serialize;
+    use crate::test_utils::assert_close;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn get_offset_cases() {
+        let area = |d: &str, distance: f64, join: LineJoin| {
+            get_area(&get_offset(&parse(d), distance, join, 4.0, 0.001))
+        };
+
+        let square = "M0 0 h10 v10 h-10 z";
+        assert_close(area(square, 1.0, LineJoin::Miter), 144.0);
+        assert_close(area(square, 1.0, LineJoin::Bevel), 142.0);
+        assert_close(area(square, 1.0, LineJoin::Round), 140.0 + PI);
+        assert_close(area(square, -1.0, LineJoin::Round), 64.0);
+        assert_close(area("M0 0 v10 h10 v-10 z", 1.0, LineJoin::Miter), 144.0);
+        assert_close(area(square, 0.0, LineJoin::Miter), 100.0);
+        assert_eq!(
+            serialize(&get_offset(
+                &parse(square),
+                1.0,
+                LineJoin::Miter,
+                4.0,
+                0.001
+            )),
+            "M-1 -1 L11 -1 L11 11 L-1 11 Z"
+        );
+
+        // Vanishing under a large inset
+        assert_eq!(
+            get_offset(&parse(square), -6.0, LineJoin::Miter, 4.0, 0.001),
+            vec![]
+        );
+
+        // Concave corners and holes
+        let l = "M0 0 h20 v10 h-10 v10 h-10 z";
+        assert_close(area(l, -1.0, LineJoin::Miter), 224.0);
+        assert_close(area(l, 1.0, LineJoin::Miter), 384.0);
+        let holed = "M0 0 h20 v20 h-20 z M5 5 v10 h10 v-10 z";
+        assert_close(area(holed, 1.0, LineJoin::Miter), 484.0 - 64.0);
+        assert_close(area(holed, 5.0, LineJoin::Miter), 900.0);
+
+        // Overlapping subpaths merge
+        let two = "M0 0 h10 v10 h-10 z M5 0 h10 v10 h-10 z";
+        assert_close(area(two, 0.0, LineJoin::Miter), 150.0);
+
+        // Subpaths inside the fill are skipped, even when they start there
+        let inner = "M0 0 h20 v20 h-20 z M5 5 h10 v10 h-10 z";
+        assert_close(area(inner, 1.0, LineJoin::Miter), 484.0);
+        assert_close(area(inner, -1.0, LineJoin::Miter), 324.0);
+        let sticking_out = "M0 0 h10 v10 h-10 z M2 2 h6 v12 h-6 z";
+        assert_close(area(sticking_out, 0.0, LineJoin::Miter), 124.0);
+        assert_close(area(sticking_out, 1.0, LineJoin::Miter), 176.0);
+        assert_eq!(
+            get_offset(&parse("M0 0 h10 h-10 z"), 1.0, LineJoin::Miter, 4.0, 0.001),
+            vec![]
+        );
+
+        let circle = "M10 0 A10 10 0 0 1 -10 0 A10 10 0 0 1 10 0 z";
+        assert_close(area(circle, 2.0, LineJoin::Miter), 144.0 * PI);
+        assert_close(area(circle, -2.0, LineJoin::Miter), 64.0 * PI);
+    }
+}
@@ -0,0 +1,498 @@
+use std::f64::consts::PI;
+
+use crate::stroke::{LineCap, LineJoin};
+use crate::vector::{Arc, Bezier3, Curve, Lerpable, Line, Vector2};
+
+// Unit normal of the direction turned by +90 degrees, to the right on screen
+pub(crate) fn get_normal(d: Vector2) -> Vector2 {
+    let norm = d.norm();
+    Vector2(-d.1 / norm, d.0 / norm)
+}
+
+fn get_unit(d: Vector2) -> Vector2 {
+    d.multi(1.0 / d.norm())
+}
+
+// Tangent that looks past cusps and coincident control points
+pub(crate) fn get_tangent(curve: &Curve, t: f64) -> Vector2 {
+    if t == 0.0 {
+        return curve.get_start_tangent();
+    }
+    if t == 1.0 {
+        return curve.get_end_tangent();
+    }
+    let d = curve.derivative(t);
+    if d.norm() > 0.0 {
+        d
+    } else {
+        curve.derivative((t + 1e-9).min(1.0))
+    }
+}
+
+// Curves running `dist` away from `curve` on the side of `get_normal`.
+// Lines and circular arcs are offset exactly, the others are approximated by
+// cubics subdivided until they are within `tolerance`.
+pub(crate) fn offset_curve(curve: &Curve, dist: f64, tolerance: f64) -> Vec<Curve> {
+    match curve {
+        Curve::Line(c) => {
+            let n = get_normal(c.p1() - c.p0()).multi(dist);
+            vec![Curve::Line(Line::new(c.p0() + n, c.p1() + n))]
+        }
+        Curve::Arc(c) if c.rx() == c.ry() => vec![offset_circular_arc(c, dist)],
+        _ => {
+            // Quarter turns of elliptical arcs are well approximated by cubics
+            let n = match curve {
+                Curve::Arc(c) => ((c.dtheta().abs() / (PI / 2.0)).ceil() as usize).max(1),
+                _ => 1,
+            };
+            let mut ret = vec![];
+            for i in 0..n {
+                let t0 = i as f64 / n as f64;
+                let t1 = (i + 1) as f64 / n as f64;
+                approximate_offset(curve, dist, tolerance, t0, t1, 0, &mut ret);
+            }
+            ret
+        }
+    }
+}
+
+// Offsets of the curves connected by joins
+pub(crate) fn get_side(
+    curves: &[Curve],
+    dist: f64,
+    join: LineJoin,
+    miter_limit: f64,
+    tolerance: f64,
+    closed: bool,
+) -> Vec<Curve> {
+    let mut ret = vec![];
+    for (i, c) in curves.iter().enumerate() {
+        if i > 0 {
+            ret.extend(get_join(&curves[i - 1], c, dist, join, miter_limit));
+        }
+        ret.extend(offset_curve(c, dist, tolerance));
+    }
+    if closed {
+        ret.extend(get_join(
+            &curves[curves.len() - 1],
+            &curves[0],
+            dist,
+            join,
+            miter_limit,
+        ));
+    }
+    ret
+}
+
+fn offset_circular_arc(arc: &Arc, dist: f64) -> Curve {
+    // The normal points to the center when the arc turns positively
+    let r = arc.rx() - dist * arc.dtheta().signum();
+    if r.abs() <= arc.rx() * 1e-12 {
+        return Curve::Line(Line::new(arc.center(), arc.center()));
+    }
+
+    // A negative radius flips the offset to the other side of the center
+    let theta = if r < 0.0 {
+        arc.theta() + PI
+    } else {
+        arc.theta()
+    };
+    Curve::Arc(Arc::from_center(
+        arc.center(),
+        r.abs(),
+        r.abs(),
+        arc.rotation(),
+        theta,
+        arc.dtheta(),
+    ))
+}
+
+// Fits a cubic sharing the end points and tangents of the offset, with its
+// handles chosen to pass through the offset at the middle
+fn approximate_offset(
+    curve: &Curve,
+    dist: f64,
+    tolerance: f64,
+    t0: f64,
+    t1: f64,
+    depth: usize,
+    ret: &mut Vec<Curve>,
+) {
+    let offset_at = |t: f64| curve.lerp(t) + get_normal(get_tangent(curve, t)).multi(dist);
+    let (q0, q1) = (offset_at(t0), offset_at(t1));
+    let u0 = get_unit(get_tangent(curve, t0));
+    let u1 = get_unit(get_tangent(curve, t1));
+
+    // B(1/2) = (q0 + q1) / 2 + 3/8 * (alpha * u0 - beta * u1)
+    let r = (offset_at((t0 + t1) / 2.0) - (q0 + q1).multi(0.5)).multi(1.0 / 0.375);
+    let cross = u0.cross(u1);
+    let chord = (q1 - q0).norm() / 3.0;
+    let (alpha, beta) = if cross.abs() > 1e-6 {
+        (r.cross(u1) / cross, -u0.cross(r) / cross)
+    } else {
+        (chord, chord)
+    };
+    let (alpha, beta) = if alpha < 0.0 || beta < 0.0 {
+        (chord, chord)
+    } else {
+        (alpha, beta)
+    };
+
+    let fitted = Curve::Bezier3(Bezier3::new(
+        q0,
+        q0 + u0.multi(alpha),
+        q1 - u1.multi(beta),
+        q1,
+    ));
+    let fits = [0.25, 0.5, 0.75].iter().all(|s| {
+        let p = offset_at(t0 + (t1 - t0) * s);
+        (fitted.lerp(fitted.get_closest_t(p)) - p).norm() <= tolerance
+    });
+
+    if fits || depth >= 12 {
+        ret.push(fitted);
+    } else {
+        let tm = (t0 + t1) / 2.0;
+        approximate_offset(curve, dist, tolerance, t0, tm, depth + 1, ret);
+        approximate_offset(curve, dist, tolerance, tm, t1, depth + 1, ret);
+    }
+}
+
+// Curves connecting the offsets of `from` and `to` at their shared vertex.
+// The inner side of the corner goes through the vertex, which is covered by
+// the stroke anyway.
+pub(crate) fn get_join(
+    from: &Curve,
+    to: &Curve,
+    dist: f64,
+    join: LineJoin,
+    miter_limit: f64,
+) -> Vec<Curve> {
+    let v = from.end();
+    let d0 = get_unit(from.get_end_tangent());
+    let d1 = get_unit(to.get_start_tangent());
+    let a = v + get_normal(d0).multi(dist);
+    let b = v + get_normal(d1).multi(dist);
+    let hw = dist.abs();
+    if (a - b).norm() <= hw * 1e-12 {
+        return vec![];
+    }
+
+    let turn = d0.cross(d1);
+    if turn * dist > 0.0 {
+        return vec![Curve::Line(Line::new(a, v)), Curve::Line(Line::new(v, b))];
+    }
+
+    let corner = Corner {
+        v,
+        a,
+        b,
+        d0,
+        d1,
+        hw,
+        miter_limit,
+    };
+    match join {
+        LineJoin::Bevel => vec![Curve::Line(Line::new(a, b))],
+        LineJoin::Round => vec![get_arc(v, hw, a, b, d0)],
+        LineJoin::Miter => corner.get_miter(false),
+        LineJoin::MiterClip => corner.get_miter(true),
+        LineJoin::Arcs => corner.get_arcs(from, to, dist),
+    }
+}
+
+// The outer side of a join from `a` to `b` around the vertex `v`
+struct Corner {
+    v: Vector2,
+    a: Vector2,
+    b: Vector2,
+    d0: Vector2,
+    d1: Vector2,
+    hw: f64,
+    miter_limit: f64,
+}
+
+impl Corner {
+    fn get_miter(&self, clip: bool) -> Vec<Curve> {
+        let Corner {
+            v, a, b, d0, d1, ..
+        } = *self;
+        let turn = d0.cross(d1);
+        if turn != 0.0 {
+            let tip = a + d0.multi((b - a).cross(d1) / turn);
+            if (tip - v).norm() <= self.miter_limit * self.hw {
+                return vec![
+                    Curve::Line(Line::new(a, tip)),
+                    Curve::Line(Line::new(tip, b)),
+                ];
+            }
+        }
+        if !clip {
+            return vec![Curve::Line(Line::new(a, b))];
+        }
+
+        // https://www.w3.org/TR/svg-strokes/#StrokeLinejoinProperty
+        // The miter is cut perpendicular to its direction at half the miter
+        // limit times the stroke width from the vertex
+        let m = get_unit(d0 - d1);
+        let limit = self.miter_limit * self.hw;
+        if (a - v).dot(m) >= limit {
+            return vec![Curve::Line(Line::new(a, b))];
+        }
+        let pa = a + d0.multi((limit - (a - v).dot(m)) / d0.dot(m));
+        let pb = b - d1.multi(((b - v).dot(m) - limit) / d1.dot(m));
+        vec![
+            Curve::Line(Line::new(a, pa)),
+            Curve::Line(Line::new(pa, pb)),
+            Curve::Line(Line::new(pb, b)),
+        ]
+    }
+
+    // Extends the offsets along their osculating circles, or lines where they
+    // are straight, until they meet. Falls back to miter-clip when they don't
+    // meet within the miter limit.
+    fn get_arcs(&self, from: &Curve, to: &Curve, dist: f64) -> Vec<Curve> {
+        let Corner {
+            v, a, b, d0, d1, ..
+        } = *self;
+        let circle = |k: f64, d: Vector2| {
+            if k.abs() * self.hw < 1e-9 {
+                None
+            } else {
+                let rho = 1.0 / k;
+                Some((v + get_normal(d).multi(rho), (rho - dist).abs()))
+            }
+        };
+        let ca = circle(from.get_curvature(1.0), d0);
+        let cb = circle(to.get_curvature(0.0), d1);
+
+        let candidates: Vec<Vector2> = match (ca, cb) {
+            (None, None) => {
+                let turn = d0.cross(d1);
+                if turn == 0.0 {
+                    vec![]
+                } else {
+                    vec![a + d0.multi((b - a).cross(d1) / turn)]
+                }
+            }
+            (Some((c, r)), None) => intersect_line_circle(b, d1, c, r),
+            (None, Some((c, r))) => intersect_line_circle(a, d0, c, r),
+            (Some((c0, r0)), Some((c1, r1))) => intersect_circles(c0, r0, c1, r1),
+        };
+        let tip = candidates
+            .into_iter()
+            .filter(|p| (*p - a).dot(d0) >= 0.0 && (b - *p).dot(d1) >= 0.0)
+            .min_by(|p, q| (*p - v).norm().partial_cmp(&(*q - v).norm()).unwrap());
+
+        match tip {
+            Some(tip) if (tip - v).norm() <= self.miter_limit * self.hw => {
+                let first = match ca {
+                    Some((c, r)) => get_arc(c, r, a, tip, d0),
+                    None => Curve::Line(Line::new(a, tip)),
+                };
+                let second = match cb {
+                    Some((c, r)) => get_arc(c, r, b, tip, d1.multi(-1.0)).reverse(),
+                    None => Curve::Line(Line::new(tip, b)),
+                };
+                vec![first, second]
+            }
+            _ => self.get_miter(true),
+        }
+    }
+}
+
+fn intersect_line_circle(p: Vector2, d: Vector2, c: Vector2, r: f64) -> Vec<Vector2> {
+    // |p + d * s - c| = r with a unit `d`
+    let q = p - c;
+    let b = q.dot(d);
+    let disc = b * b - (q.dot(q) - r * r);
+    if disc < 0.0 {
+        return vec![];
+    }
+    let sq = disc.sqrt();
+    vec![p + d.multi(-b - sq), p + d.multi(-b + sq)]
+}
+
+fn intersect_circles(c0: Vector2, r0: f64, c1: Vector2, r1: f64) -> Vec<Vector2> {
+    let e = c1 - c0;
+    let l = e.norm();
+    if l == 0.0 || l > r0 + r1 || l < (r0 - r1).abs() {
+        return vec![];
+    }
+    let x = (l * l + r0 * r0 - r1 * r1) / (2.0 * l);
+    let h = (r0 * r0 - x * x).max(0.0).sqrt();
+    let u = e.multi(1.0 / l);
+    let base = c0 + u.multi(x);
+    let n = Vector2(-u.1, u.0);
+    vec![base + n.multi(h), base - n.multi(h)]
+}
+
+// Circular arc around `c` from `p` to `q`, leaving `p` toward `dir`
+pub(crate) fn get_arc(c: Vector2, r: f64, p: Vector2, q: Vector2, dir: Vector2) -> Curve {
+    let (u, w) = (p - c, q - c);
+    let theta = u.1.atan2(u.0);
+    let mut dtheta = w.1.atan2(w.0) - theta;
+    if u.cross(dir) >= 0.0 {
+        while dtheta < 0.0 {
+            dtheta += 2.0 * PI;
+        }
+    } else {
+        while dtheta > 0.0 {
+            dtheta -= 2.0 * PI;
+        }
+    }
+
+    let mut arc = Arc::from_center(c, r, r, 0.0, theta, dtheta);
+    arc.p0 = p;
+    arc.p1 = q;
+    Curve::Arc(arc)
+}
+
+// Curves from the offset by `dist` at the end `e` to the offset by `-dist`,
+// where `d` points away from the stroke
+pub(crate) fn get_cap(e: Vector2, d: Vector2, dist: f64, cap: LineCap) -> Vec<Curve> {
+    let d = get_unit(d);
+    let n = get_normal(d).multi(dist);
+    let (p, q) = (e + n, e - n);
+    match cap {
+        LineCap::Butt => vec![Curve::Line(Line::new(p, q))],
+        LineCap::Round => vec![get_arc(e, dist.abs(), p, q, d)],
+        LineCap::Square => {
+            let ext = d.multi(dist.abs());
+            vec![
+                Curve::Line(Line::new(p, p + ext)),
+                Curve::Line(Line::new(p + ext, q + ext)),
+                Curve::Line(Line::new(q + ext, q)),
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::assert_close;
+
+    #[test]
+    fn offset_curve_exact() {
+        let line = Curve::Line(Line::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0)));
+        let ret = offset_curve(&line, 2.0, 0.01);
+        assert_eq!(
+            ret,
+            vec![Curve::Line(Line::new(
+                Vector2(0.0, 2.0),
+                Vector2(10.0, 2.0)
+            ))]
+        );
+
+        let arc = Curve::Arc(Arc::new(
+            Vector2(10.0, 0.0),
+            10.0,
+            10.0,
+            0.0,
+            false,
+            true,
+            Vector2(-10.0, 0.0),
+        ));
+        let inner = offset_curve(&arc, 2.0, 0.01);
+        assert_close(inner[0].start(), Vector2(8.0, 0.0));
+        assert_close(inner[0].lerp(0.5), Vector2(0.0, 8.0));
+        let outer = offset_curve(&arc, -2.0, 0.01);
+        assert_close(outer[0].end(), Vector2(-12.0, 0.0));
+
+        // Past the center
+        let flipped = offset_curve(&arc, 12.0, 0.01);
+        assert_close(flipped[0].start(), Vector2(-2.0, 0.0));
+        assert_close(flipped[0].lerp(0.5), Vector2(0.0, -2.0));
+    }
+
+    #[test]
+    fn offset_curve_approximated() {
+        let c = Curve::Bezier3(Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(0.0, -10.0),
+            Vector2(20.0, 10.0),
+            Vector2(20.0, 0.0),
+        ));
+        for dist in [-3.0, 3.0] {
+            let ret = offset_curve(&c, dist, 0.001);
+            assert_close(ret[0].start(), Vector2(dist, 0.0));
+            assert_close(ret[ret.len() - 1].end(), Vector2(20.0 + dist, 0.0));
+            for i in 0..=20 {
+                let t = i as f64 / 20.0;
+                let p = c.lerp(t) + get_normal(c.derivative(t)).multi(dist);
+                let d = ret
+                    .iter()
+                    .map(|o| (o.lerp(o.get_closest_t(p)) - p).norm())
+                    .fold(f64::INFINITY, f64::min);
+                assert!(d < 0.002, "{} at {}", d, t);
+            }
+        }
+    }
+
+    #[test]
+    fn get_join_cases() {
+        let from = Curve::Line(Line::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0)));
+        let to = Curve::Line(Line::new(Vector2(10.0, 0.0), Vector2(10.0, 10.0)));
+
+        // Inner side
+        let ret = get_join(&from, &to, 1.0, LineJoin::Miter, 4.0);
+        assert_eq!(ret.len(), 2);
+        assert_close(ret[0].end(), Vector2(10.0, 0.0));
+
+        let ret = get_join(&from, &to, -1.0, LineJoin::Miter, 4.0);
+        assert_close(ret[0].end(), Vector2(11.0, -1.0));
+        let ret = get_join(&from, &to, -1.0, LineJoin::Miter, 1.2);
+        assert_eq!(ret.len(), 1);
+
+        // Clipped at 1.2 from the vertex along the diagonal
+        let ret = get_join(&from, &to, -1.0, LineJoin::MiterClip, 1.2);
+        assert_eq!(ret.len(), 3);
+        let m = Vector2(1.0, -1.0).multi(1.0 / 2.0_f64.sqrt());
+        assert!(((ret[1].start() - Vector2(10.0, 0.0)).dot(m) - 1.2).abs() < 1e-9);
+        assert!(((ret[1].end() - Vector2(10.0, 0.0)).dot(m) - 1.2).abs() < 1e-9);
+
+        let ret = get_join(&from, &to, -1.0, LineJoin::Round, 4.0);
+        assert_close(ret[0].lerp(0.5), Vector2(10.0, 0.0) + m);
+
+        // Lines meet like miter
+        let ret = get_join(&from, &to, -1.0, LineJoin::Arcs, 4.0);
+        assert_close(ret[0].end(), Vector2(11.0, -1.0));
+
+        // A line meets the offset circle of an arc
+        let from = Curve::Line(Line::new(Vector2(0.0, 10.0), Vector2(10.0, 0.0)));
+        let to = Curve::Arc(Arc::new(
+            Vector2(10.0, 0.0),
+            10.0,
+            10.0,
+            0.0,
+            false,
+            true,
+            Vector2(10.0, 20.0),
+        ));
+        let ret = get_join(&from, &to, -1.0, LineJoin::Arcs, 4.0);
+        assert_eq!(ret.len(), 2);
+        let tip = ret[0].end();
+        assert!((tip - ret[0].start()).cross(Vector2(1.0, -1.0)).abs() < 1e-9);
+        assert!(((tip - Vector2(10.0, 10.0)).norm() - 11.0).abs() < 1e-9);
+        assert!(matches!(ret[1], Curve::Arc(_)));
+    }
+
+    #[test]
+    fn get_cap_cases() {
+        let e = Vector2(10.0, 0.0);
+        let d = Vector2(1.0, 0.0);
+        let ret = get_cap(e, d, -1.0, LineCap::Butt);
+        assert_eq!(
+            ret,
+            vec![Curve::Line(Line::new(
+                Vector2(10.0, -1.0),
+                Vector2(10.0, 1.0)
+            ))]
+        );
+        let ret = get_cap(e, d, -1.0, LineCap::Square);
+        assert_eq!(ret[1].start(), Vector2(11.0, -1.0));
+        let ret = get_cap(e, d, -1.0, LineCap::Round);
+        assert_close(ret[0].lerp(0.5), Vector2(11.0, 0.0));
+    }
+}
@@ -1,169 +1,277 @@
+use std::fmt;
+
 use crate::path::PathSegment;
+use crate::segment::{get_param_count, Segment};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    // A character that is neither a command nor a number
+    UnexpectedToken(char),
+    // The command ran out of values
+    LackOfParameter(char),
+    // A command or an invalid number in place of a value
+    UnexpectedParameter(String),
+    // Only from `PathSegment`s, whose command can be anything
+    UnexpectedCommand(char),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken(c) => write!(f, "Unexpected token: {}", c),
+            ParseError::LackOfParameter(c) => write!(f, "Lack of parameter: {}", c),
+            ParseError::UnexpectedParameter(v) => write!(f, "Unexpected parameter: {}", v),
+            ParseError::UnexpectedCommand(c) => write!(f, "Unexpected command: {}", c),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
 
 pub fn parse(d: &str) -> Vec<PathSegment> {
-    to_segments(&split(d))
+    try_parse(d).unwrap_or_else(|e| panic!("{}", e))
 }
 
-fn to_segments(src: &Vec<String>) -> Vec<PathSegment> {
+// Same as `parse` returning the message instead of panicking
+pub fn try_parse(d: &str) -> Result<Vec<PathSegment>, ParseError> {
+    let mut iter = parse_segments(d.as_bytes());
     let mut ret: Vec<PathSegment> = vec![];
-    let mut command = 'M';
-    let mut param_count = 2;
-    let mut cursor = 0;
-    let len = src.len();
-
-    while cursor < len {
-        let mut current_cursor = cursor;
-
-        match is_command(src[current_cursor].chars().next().unwrap()) {
-            Some(c) => {
-                command = c;
-                param_count = get_param_count(command);
-                current_cursor += 1;
-            }
-            None => {}
-        }
-        if current_cursor + param_count > len {
-            panic!("Lack of parameter: {}", command);
-        }
-
-        let values: Vec<f64> = src[current_cursor..(current_cursor + param_count)]
-            .iter()
-            .map(|s| match s.parse::<f64>() {
-                Ok(v) => v,
-                Err(_) => panic!("Unexpected parameter: {}", s),
-            })
-            .collect();
-        ret.push(PathSegment::new(command, values));
-        current_cursor += param_count;
-
-        if current_cursor == cursor {
-            break;
-        }
-        cursor = current_cursor;
+    // Values are kept as they are, unlike `Segment` normalizing arc flags
+    while let Some(read) = iter.read_values() {
+        let (command, values, count) = read?;
+        ret.push(PathSegment::new(command, values[..count].to_vec()));
     }
-
-    ret
+    Ok(ret)
 }
 
-fn get_param_count(command: char) -> usize {
-    match command {
-        'H' | 'h' | 'V' | 'v' => 1,
-        'M' | 'm' | 'L' | 'l' | 'T' | 't' => 2,
-        'Q' | 'q' | 'S' | 's' => 4,
-        'C' | 'c' => 6,
-        'A' | 'a' => 7,
-        _ => 0,
+// Segments read one by one without allocating. Errors are the same as
+// `try_parse` reports, after which the iteration ends.
+pub fn parse_segments(d: &[u8]) -> SegmentIter<'_> {
+    SegmentIter {
+        lexer: Lexer::new(d),
+        command: 'M',
+        done: false,
     }
 }
 
-fn is_command(c: char) -> Option<char> {
-    match c {
-        'M' | 'm' | 'L' | 'l' | 'H' | 'h' | 'V' | 'v' | 'Q' | 'q' | 'T' | 't' | 'C' | 'c' | 'S'
-        | 's' | 'A' | 'a' | 'Z' | 'z' => Some(c),
-        _ => None,
+pub struct SegmentIter<'a> {
+    lexer: Lexer<'a>,
+    // Repeated when values follow without a command
+    command: char,
+    done: bool,
+}
+
+impl Iterator for SegmentIter<'_> {
+    type Item = Result<Segment, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_values().map(|read| {
+            let (command, values, count) = read?;
+            Segment::from_values(command, &values[..count])
+        })
     }
 }
 
-type ParserFn = fn(text: &Vec<char>, index: usize) -> Option<(String, usize)>;
-static PARSER_FN: [ParserFn; 2] = [parse_number, parse_command];
+// The command with its values, the first `count` of which are used
+type RawSegment = (char, [f64; 7], usize);
 
-fn split(d: &str) -> Vec<String> {
-    let text: Vec<char> = d.chars().collect();
-    let mut cursor = 0;
-    let mut ret: Vec<String> = vec![];
+impl SegmentIter<'_> {
+    fn read_values(&mut self) -> Option<Result<RawSegment, ParseError>> {
+        if self.done {
+            return None;
+        }
+        let ret = self.read();
+        if !matches!(ret, Some(Ok(_))) {
+            self.done = true;
+        }
+        ret
+    }
 
-    cursor += drop_whitespace(&text, cursor);
-    while cursor < text.len() {
-        let mut hit = false;
+    fn read(&mut self) -> Option<Result<RawSegment, ParseError>> {
+        let mut first: Option<&str> = None;
+        match self.lexer.next()? {
+            Err(e) => return Some(Err(e)),
+            Ok(Token::Command(c)) => self.command = c,
+            Ok(Token::Number(v)) => first = Some(v),
+        }
 
-        for parser_fn in PARSER_FN {
-            hit = match parser_fn(&text, cursor) {
-                Some((value, size)) => {
-                    cursor += size;
-                    ret.push(value);
-                    true
-                }
-                None => false,
+        let count = get_param_count(self.command).unwrap_or(0);
+        if first.is_some() && count == 0 {
+            // Values after a closepath repeat it once and end the path
+            let ret = Ok((self.command, [0.0; 7], 0));
+            return Some(self.lexer.find_error().map_or(ret, Err));
+        }
+
+        let mut values = [0.0; 7];
+        for (i, value) in values.iter_mut().take(count).enumerate() {
+            let token = match first.take() {
+                Some(v) => Ok(Token::Number(v)),
+                None => match self.lexer.next() {
+                    Some(token) => token,
+                    None => return Some(Err(self.lack_of_parameter())),
+                },
+            };
+            let parsed = match token {
+                Err(e) => return Some(Err(e)),
+                Ok(Token::Number(v)) => v.parse::<f64>().map_err(|_| v.to_string()),
+                Ok(Token::Command(c)) => Err(c.to_string()),
             };
-            if hit {
-                break;
+            match parsed {
+                Ok(v) => *value = v,
+                Err(v) => {
+                    // Lacking tokens and invalid ones anywhere are reported
+                    // first
+                    let rest = match self.lexer.count_rest() {
+                        Ok(n) => n,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    return Some(Err(if i + 1 + rest < count {
+                        self.lack_of_parameter()
+                    } else {
+                        ParseError::UnexpectedParameter(v)
+                    }));
+                }
             }
         }
-        if !hit {
-            panic!("Unexpected token: {}", text.get(cursor).unwrap());
-        }
-        cursor += drop_whitespace(&text, cursor);
+        Some(Ok((self.command, values, count)))
+    }
+
+    fn lack_of_parameter(&self) -> ParseError {
+        ParseError::LackOfParameter(self.command)
     }
-    ret
 }
 
-fn drop_whitespace(text: &Vec<char>, index: usize) -> usize {
-    let mut cursor = index;
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Token<'a> {
+    Command(char),
+    // Without the leading '+'
+    Number(&'a str),
+}
 
-    while cursor < text.len() {
-        match text.get(cursor) {
-            Some(' ' | ',') => {
-                cursor += 1;
-            }
-            _ => {
-                break;
-            }
+// Also reads the number lists of SVG attributes, so that they're split
+// the same way as path data
+pub(crate) struct Lexer<'a> {
+    d: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let d = self.d;
+        while self.cursor < d.len() && is_whitespace(d[self.cursor]) {
+            self.cursor += 1;
+        }
+        let begin = self.cursor;
+        let c = *d.get(begin)?;
+
+        if let Some(end) = scan_number(d, begin) {
+            self.cursor = end;
+            let begin = if c == b'+' { begin + 1 } else { begin };
+            // Numbers are ASCII
+            let text = std::str::from_utf8(&d[begin..end]).unwrap();
+            return Some(Ok(Token::Number(text)));
         }
+        if is_command(c) {
+            self.cursor += 1;
+            return Some(Ok(Token::Command(c as char)));
+        }
+
+        self.cursor = d.len();
+        Some(Err(ParseError::UnexpectedToken(get_char(d, begin))))
     }
-    cursor - index
 }
 
-fn parse_number(text: &Vec<char>, index: usize) -> Option<(String, usize)> {
-    let mut cursor = index;
-    let mut value: String = String::new();
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(d: &'a [u8]) -> Self {
+        Lexer { d, cursor: 0 }
+    }
 
-    match text.get(cursor) {
-        Some('-') => {
-            cursor += 1;
-            value.push('-');
-        }
-        Some('+') => {
-            cursor += 1;
+    // Number of the remaining tokens, or the first invalid one
+    fn count_rest(&mut self) -> Result<usize, ParseError> {
+        let mut n = 0;
+        for token in self {
+            token?;
+            n += 1;
         }
-        _ => {}
+        Ok(n)
     }
 
-    while cursor < text.len() {
-        match text.get(cursor) {
-            Some(c) => match c {
-                '0'..='9' => {
-                    cursor += 1;
-                    value.push(*c);
-                }
-                '.' => {
-                    cursor += 1;
-                    value.push('.');
-                }
-                _ => {
-                    break;
-                }
-            },
-            _ => {
-                break;
-            }
+    fn find_error(&mut self) -> Option<ParseError> {
+        self.count_rest().err()
+    }
+}
+
+fn is_whitespace(c: u8) -> bool {
+    matches!(c, b' ' | b',' | b'\t' | b'\n' | b'\r' | b'\x0C')
+}
+
+fn is_command(c: u8) -> bool {
+    matches!(
+        c,
+        b'M' | b'm'
+            | b'L'
+            | b'l'
+            | b'H'
+            | b'h'
+            | b'V'
+            | b'v'
+            | b'Q'
+            | b'q'
+            | b'T'
+            | b't'
+            | b'C'
+            | b'c'
+            | b'S'
+            | b's'
+            | b'A'
+            | b'a'
+            | b'Z'
+            | b'z'
+    )
+}
+
+// An optional sign followed by digits and dots, which may not make a valid
+// number, and an exponent when digits follow the 'e'
+pub(crate) fn scan_number(d: &[u8], index: usize) -> Option<usize> {
+    let mut cursor = skip_sign(d, index);
+    while matches!(d.get(cursor), Some(b'0'..=b'9' | b'.')) {
+        cursor += 1;
+    }
+    if cursor == index {
+        return None;
+    }
+    if matches!(d.get(cursor), Some(b'e' | b'E')) {
+        let digits = skip_sign(d, cursor + 1);
+        let end = digits
+            + d[digits..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+        if end != digits {
+            cursor = end;
         }
     }
-    if cursor != index {
-        Some((value, cursor - index))
+    Some(cursor)
+}
+
+fn skip_sign(d: &[u8], index: usize) -> usize {
+    if matches!(d.get(index), Some(b'-' | b'+')) {
+        index + 1
     } else {
-        None
+        index
     }
 }
 
-fn parse_command(text: &Vec<char>, index: usize) -> Option<(String, usize)> {
-    match text.get(index) {
-        Some(c) => match c {
-            'M' | 'm' | 'L' | 'l' | 'H' | 'h' | 'V' | 'v' | 'Q' | 'q' | 'T' | 't' | 'C' | 'c'
-            | 'S' | 's' | 'A' | 'a' | 'Z' | 'z' => Some((String::from(*c), 1)),
-            _ => None,
-        },
-        _ => None,
-    }
+// The character at `index` for messages, or the byte itself when it isn't
+// valid UTF-8
+fn get_char(d: &[u8], index: usize) -> char {
+    let end = (index + 4).min(d.len());
+    (index + 1..=end)
+        .find_map(|e| std::str::from_utf8(&d[index..e]).ok())
+        .and_then(|s| s.chars().next())
+        .unwrap_or(d[index] as char)
 }
 
 #[cfg(test)]
@@ -173,7 +281,7 @@ mod tests {
     #[test]
     fn to_segments_cases() {
         assert_eq!(
-            to_segments(&split("M 12 9 L1 -2Z")),
+            parse("M 12 9 L1 -2Z"),
             vec![
                 PathSegment::new('M', vec![12.0, 9.0]),
                 PathSegment::new('L', vec![1.0, -2.0]),
@@ -182,7 +290,7 @@ mod tests {
         );
 
         assert_eq!(
-            to_segments(&split("m 1 2 l3 4z")),
+            parse("m 1 2 l3 4z"),
             vec![
                 PathSegment::new('m', vec![1.0, 2.0]),
                 PathSegment::new('l', vec![3.0, 4.0]),
@@ -191,7 +299,7 @@ mod tests {
         );
 
         assert_eq!(
-            to_segments(&split("H 1 V 2 h 3 v 4")),
+            parse("H 1 V 2 h 3 v 4"),
             vec![
                 PathSegment::new('H', vec![1.0]),
                 PathSegment::new('V', vec![2.0]),
@@ -201,7 +309,7 @@ mod tests {
         );
 
         assert_eq!(
-            to_segments(&split("Q 1 2 3 4 q 1 2 3 4")),
+            parse("Q 1 2 3 4 q 1 2 3 4"),
             vec![
                 PathSegment::new('Q', vec![1.0, 2.0, 3.0, 4.0]),
                 PathSegment::new('q', vec![1.0, 2.0, 3.0, 4.0]),
@@ -209,7 +317,7 @@ mod tests {
         );
 
         assert_eq!(
-            to_segments(&split("T 1 2 t 1 2")),
+            parse("T 1 2 t 1 2"),
             vec![
                 PathSegment::new('T', vec![1.0, 2.0]),
                 PathSegment::new('t', vec![1.0, 2.0]),
@@ -217,7 +325,7 @@ mod tests {
         );
 
         assert_eq!(
-            to_segments(&split("C 1 2 3 4 5 6 c 1 2 3 4 5 6")),
+            parse("C 1 2 3 4 5 6 c 1 2 3 4 5 6"),
             vec![
                 PathSegment::new('C', vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
                 PathSegment::new('c', vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
@@ -225,7 +333,7 @@ mod tests {
         );
 
         assert_eq!(
-            to_segments(&split("S 1 2 3 4 s 1 2 3 4")),
+            parse("S 1 2 3 4 s 1 2 3 4"),
             vec![
                 PathSegment::new('S', vec![1.0, 2.0, 3.0, 4.0]),
                 PathSegment::new('s', vec![1.0, 2.0, 3.0, 4.0]),
@@ -233,7 +341,7 @@ mod tests {
         );
 
         assert_eq!(
-            to_segments(&split("A 1 2 3 4 5 6 7 a 1 2 3 4 5 6 7")),
+            parse("A 1 2 3 4 5 6 7 a 1 2 3 4 5 6 7"),
             vec![
                 PathSegment::new('A', vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]),
                 PathSegment::new('a', vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]),
@@ -244,7 +352,7 @@ mod tests {
     #[test]
     fn to_segments_inherit_previous_command() {
         assert_eq!(
-            to_segments(&split("L 12 9 1 2 H 1 2")),
+            parse("L 12 9 1 2 H 1 2"),
             vec![
                 PathSegment::new('L', vec![12.0, 9.0]),
                 PathSegment::new('L', vec![1.0, 2.0]),
@@ -257,13 +365,26 @@ mod tests {
     #[test]
     #[should_panic(expected = "Unexpected parameter: L")]
     fn to_segments_panic_for_invalid_parameter() {
-        to_segments(&split("L 1 L 2"));
+        parse("L 1 L 2");
     }
 
     #[test]
     #[should_panic(expected = "Lack of parameter: L")]
     fn to_segments_panic_for_lack_of_parameter() {
-        to_segments(&split("L 1"));
+        parse("L 1");
+    }
+
+    fn split(d: &str) -> Vec<String> {
+        Lexer {
+            d: d.as_bytes(),
+            cursor: 0,
+        }
+        .map(|t| match t {
+            Ok(Token::Command(c)) => c.to_string(),
+            Ok(Token::Number(v)) => v.to_string(),
+            Err(e) => panic!("{}", e),
+        })
+        .collect()
     }
 
     #[test]
@@ -273,6 +394,7 @@ mod tests {
         assert_eq!(split("M,M"), vec!["M", "M"]);
         assert_eq!(split("M,,M"), vec!["M", "M"]);
         assert_eq!(split(" M, ,M "), vec!["M", "M"]);
+        assert_eq!(split("M\n\tM\r\n"), vec!["M", "M"]);
     }
 
     #[test]
@@ -283,6 +405,8 @@ mod tests {
         assert_eq!(split("M -12-9"), vec!["M", "-12", "-9"]);
         assert_eq!(split("M +12+9"), vec!["M", "12", "9"]);
         assert_eq!(split("M -1.2 1"), vec!["M", "-1.2", "1"]);
+        assert_eq!(split("M 1e2 -1.5E-3"), vec!["M", "1e2", "-1.5E-3"]);
+        assert_eq!(split("M 1e+2-1"), vec!["M", "1e+2", "-1"]);
     }
 
     #[test]
@@ -311,6 +435,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn try_parse_cases() {
+        assert_eq!(
+            try_parse("M1 2z"),
+            Ok(vec![
+                PathSegment::new('M', vec![1.0, 2.0]),
+                PathSegment::new('z', vec![]),
+            ])
+        );
+        assert_eq!(try_parse("L 1"), Err(ParseError::LackOfParameter('L')));
+        assert_eq!(try_parse("M 1 K"), Err(ParseError::UnexpectedToken('K')));
+        assert_eq!(
+            try_parse("M1 2z 3 4"),
+            Ok(vec![
+                PathSegment::new('M', vec![1.0, 2.0]),
+                PathSegment::new('z', vec![]),
+                PathSegment::new('z', vec![]),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_segments_cases() {
+        use crate::vector::Vector2;
+
+        let ret: Vec<Result<Segment, ParseError>> = parse_segments(b"M1 2 h3 4z").collect();
+        assert_eq!(
+            ret,
+            vec![
+                Ok(Segment::MoveTo {
+                    abs: true,
+                    to: Vector2(1.0, 2.0)
+                }),
+                Ok(Segment::HorizontalTo { abs: false, x: 3.0 }),
+                Ok(Segment::HorizontalTo { abs: false, x: 4.0 }),
+                Ok(Segment::Close { abs: false }),
+            ]
+        );
+
+        // Segments before the error are yielded
+        let ret: Vec<Result<Segment, ParseError>> = parse_segments(b"M1 2 L 1 L 2 3").collect();
+        assert_eq!(ret.len(), 2);
+        assert_eq!(
+            ret[1],
+            Err(ParseError::UnexpectedParameter("L".to_string()))
+        );
+        let ret: Vec<Result<Segment, ParseError>> = parse_segments(b"M1 2 L 1 K").collect();
+        assert_eq!(ret[1..], [Err(ParseError::UnexpectedToken('K'))]);
+        assert_eq!(parse_segments(b"z 1 2").count(), 2);
+        assert_eq!(
+            parse_segments(b"z 1 K").last(),
+            Some(Err(ParseError::UnexpectedToken('K')))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unexpected token: e")]
+    fn split_panic_for_exponent_without_digits() {
+        split("M 1e-");
+    }
+
     #[test]
     #[should_panic(expected = "Unexpected token: K")]
     fn split_panic_for_unexpected_token() {
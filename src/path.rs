@@ -1,7 +1,14 @@
-use crate::vector::{Arc, Bezier2, Bezier3, Lerpable, Vector2};
+use std::convert::TryFrom;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+use crate::geometry::{iter_geometry, Geometry, GeometryIter, Primitive};
+use crate::parser::{parse_segments, ParseError};
+use crate::segment::Segment;
+use crate::vector::{Curve, Lerpable, Vector2};
 
 // https://svgwg.org/specs/paths/#InterfaceSVGPathSegment
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PathSegment {
     pub _type: char,
     pub values: Vec<f64>,
@@ -11,306 +18,200 @@ impl PathSegment {
     pub fn new(_type: char, values: Vec<f64>) -> Self {
         PathSegment { _type, values }
     }
-}
 
-pub fn get_path_length(segments: &Vec<PathSegment>) -> f64 {
-    let mut length: f64 = 0.0;
-    let mut start: Option<Vector2> = None;
-    let mut current = Vector2(0.0, 0.0);
-    let mut control: Option<Vector2> = None;
-
-    for seg in segments {
-        match seg._type {
-            'Z' | 'z' => match start {
-                Some(s) => {
-                    length += (current - s).norm();
-                    current = s;
-                    control = None;
-                }
-                _ => {}
-            },
-            'M' => {
-                let p = get_point_m(&seg.values);
-                start = Some(p);
-                current = p;
-                control = None;
-            }
-            'm' => {
-                let p = get_point_m(&seg.values) + current;
-                start = Some(p);
-                current = p;
-                control = None;
-            }
-            'L' => {
-                if start.is_none() {
-                    let p = get_point_m(&seg.values);
-                    start = Some(p);
-                    current = p;
-                } else {
-                    let (d, p) = get_length_l(&seg.values, &current);
-                    length += d;
-                    current = p;
-                }
-                control = None;
-            }
-            'l' => {
-                if start.is_none() {
-                    let p = get_point_m(&seg.values);
-                    start = Some(p);
-                    current = p;
-                } else {
-                    let (d, p) = get_length_l_relative(&seg.values, &current);
-                    length += d;
-                    current = p;
-                }
-                control = None;
-            }
-            'H' | 'h' => {
-                let (d, p) = get_length_h(&seg.values, &current);
-                length += d;
-                current = p;
-                control = None;
-            }
-            'V' | 'v' => {
-                let (d, p) = get_length_v(&seg.values, &current);
-                length += d;
-                current = p;
-                control = None;
-            }
-            'Q' => {
-                let (d, p1, p2) = get_length_q(&seg.values, &current);
-                length += d;
-                current = p2;
-                control = Some(p1);
-            }
-            'q' => {
-                let (d, p1, p2) = get_length_q_relative(&seg.values, &current);
-                length += d;
-                current = p2;
-                control = Some(p1);
-            }
-            'T' => {
-                let (d, p1, p2) = get_length_t(&seg.values, &current, &control.unwrap_or(current));
-                length += d;
-                current = p2;
-                control = Some(p1);
-            }
-            't' => {
-                let (d, p1, p2) =
-                    get_length_t_relative(&seg.values, &current, &control.unwrap_or(current));
-                length += d;
-                current = p2;
-                control = Some(p1);
+    // Absolute segment drawing the curve from its start point
+    pub fn from_curve(curve: &Curve) -> Self {
+        match curve {
+            Curve::Line(c) => PathSegment::new('L', vec![c.p1.0, c.p1.1]),
+            Curve::Bezier2(c) => PathSegment::new('Q', vec![c.p1.0, c.p1.1, c.p2.0, c.p2.1]),
+            Curve::Bezier3(c) => {
+                PathSegment::new('C', vec![c.p1.0, c.p1.1, c.p2.0, c.p2.1, c.p3.0, c.p3.1])
             }
-            'C' => {
-                let (d, p1, p2) = get_length_c(&seg.values, &current);
-                length += d;
-                current = p2;
-                control = Some(p1);
-            }
-            'c' => {
-                let (d, p1, p2) = get_length_c_relative(&seg.values, &current);
-                length += d;
-                current = p2;
-                control = Some(p1);
-            }
-            'S' => {
-                let (d, p1, p2) = get_length_s(&seg.values, &current, &control.unwrap_or(current));
-                length += d;
-                current = p2;
-                control = Some(p1);
-            }
-            's' => {
-                let (d, p1, p2) =
-                    get_length_s_relative(&seg.values, &current, &control.unwrap_or(current));
-                length += d;
-                current = p2;
-                control = Some(p1);
-            }
-            'A' => {
-                let (d, p1) = get_length_a(&seg.values, &current);
-                length += d;
-                current = p1;
-                control = None;
-            }
-            'a' => {
-                let (d, p1) = get_length_a_relative(&seg.values, &current);
-                length += d;
-                current = p1;
-                control = None;
-            }
-            _ => {}
-        };
+            Curve::Arc(c) => PathSegment::new(
+                'A',
+                vec![
+                    c.rx,
+                    c.ry,
+                    c.rotation.to_degrees(),
+                    if c.dtheta.abs() > PI { 1.0 } else { 0.0 },
+                    if c.dtheta > 0.0 { 1.0 } else { 0.0 },
+                    c.p1.0,
+                    c.p1.1,
+                ],
+            ),
+        }
     }
-
-    length
-}
-
-fn get_number(values: &Vec<f64>, i: usize) -> f64 {
-    *values.get(i).unwrap()
-}
-
-fn get_vector(values: &Vec<f64>, xi: usize, yi: usize) -> Vector2 {
-    Vector2(get_number(values, xi), get_number(values, yi))
 }
 
-fn get_bool(values: &Vec<f64>, i: usize) -> bool {
-    get_number(values, i) != 0.0
+// A closed subpath tracing the curves. The last line is left to the closepath.
+pub(crate) fn from_closed_curves(curves: &[Curve]) -> Vec<PathSegment> {
+    let first = match curves.first() {
+        Some(c) => c.start(),
+        None => return vec![],
+    };
+    let drawn = match curves.last() {
+        Some(Curve::Line(_)) => &curves[..curves.len() - 1],
+        _ => curves,
+    };
+
+    let mut ret = vec![PathSegment::new('M', vec![first.0, first.1])];
+    ret.extend(drawn.iter().map(PathSegment::from_curve));
+    ret.push(PathSegment::new('Z', vec![]));
+    ret
 }
 
-fn get_point_m(values: &Vec<f64>) -> Vector2 {
-    get_vector(values, 0, 1)
+pub fn get_path_length(segments: &[PathSegment]) -> f64 {
+    get_geometry_length(iter_geometry(segments))
 }
 
-fn get_length_l(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2) {
-    let v = get_vector(values, 0, 1);
-    ((v - *from).norm(), v)
+// Length of the path data measured while parsing it, or the first error
+pub fn get_data_length(d: &str) -> Result<f64, ParseError> {
+    let mut error: Option<ParseError> = None;
+    let segments = parse_segments(d.as_bytes()).map_while(|s| s.map_err(|e| error = Some(e)).ok());
+    let length = get_geometry_length(GeometryIter::new(segments));
+    error.map_or(Ok(length), Err)
 }
 
-fn get_length_l_relative(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2) {
-    let v = get_vector(values, 0, 1);
-    (v.norm(), v + *from)
+fn get_geometry_length(geometry: impl Iterator<Item = Geometry>) -> f64 {
+    geometry
+        .filter_map(|g| g.primitive.to_curve())
+        .map(|c| c.get_appro_length(SPLIT_COUNT))
+        .sum()
 }
 
-fn get_length_h(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2) {
-    let d = get_number(values, 0);
-    (d, Vector2(d + from.0, from.1))
+// Lengths of the path data, where invalid ones don't affect the others
+pub fn get_path_lengths(ds: &[&str]) -> Vec<Result<f64, ParseError>> {
+    ds.iter().map(|d| get_data_length(d)).collect()
 }
 
-fn get_length_v(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2) {
-    let d = get_number(values, 0);
-    (d, Vector2(from.0, d + from.1))
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ClosestPoint {
+    pub point: Vector2,
+    pub distance: f64,
+    // Index of the segment having the point, 'Z' for closing lines
+    pub index: usize,
+    // Parameter on the segment's curve
+    pub t: f64,
+    // Distance from the start of the path, skipping moves between subpaths
+    pub length: f64,
 }
 
-static SPLIT_COUNT: usize = 20;
-
-fn get_length_q(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2, Vector2) {
-    let p1 = get_vector(values, 0, 1);
-    let p2 = get_vector(values, 2, 3);
-    (
-        Bezier2::new(*from, p1, p2).get_appro_length(SPLIT_COUNT),
-        p1,
-        p2,
-    )
-}
-
-fn get_length_q_relative(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2, Vector2) {
-    let p1 = get_vector(values, 0, 1) + *from;
-    let p2 = get_vector(values, 2, 3) + *from;
-    (
-        Bezier2::new(*from, p1, p2).get_appro_length(SPLIT_COUNT),
-        p1,
-        p2,
-    )
-}
-
-fn get_length_t(values: &Vec<f64>, from: &Vector2, control: &Vector2) -> (f64, Vector2, Vector2) {
-    let p1 = from.multi(2.0) - *control;
-    let p2 = get_vector(values, 0, 1);
-    (
-        Bezier2::new(*from, p1, p2).get_appro_length(SPLIT_COUNT),
-        p1,
-        p2,
-    )
-}
-
-fn get_length_t_relative(
-    values: &Vec<f64>,
-    from: &Vector2,
-    control: &Vector2,
-) -> (f64, Vector2, Vector2) {
-    let p1 = from.multi(2.0) - *control;
-    let p2 = get_vector(values, 0, 1) + *from;
-    (
-        Bezier2::new(*from, p1, p2).get_appro_length(SPLIT_COUNT),
-        p1,
-        p2,
-    )
-}
+// The point of the path closest to `p`. `None` when the path draws nothing.
+pub fn get_closest_point(segments: &[PathSegment], p: Vector2) -> Option<ClosestPoint> {
+    let mut ret: Option<ClosestPoint> = None;
+    let mut offset = 0.0;
+
+    for subpath in get_subpaths(segments) {
+        for (index, curve) in &subpath.curves {
+            let t = curve.get_closest_t(p);
+            let point = curve.lerp(t);
+            let distance = (point - p).norm();
+            if ret.is_none_or(|r| distance < r.distance) {
+                ret = Some(ClosestPoint {
+                    point,
+                    distance,
+                    index: *index,
+                    t,
+                    length: offset + curve.get_appro_length_at_t(t, SPLIT_COUNT),
+                });
+            }
+            offset += curve.get_appro_length(SPLIT_COUNT);
+        }
+    }
 
-fn get_length_c(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2, Vector2) {
-    let p1 = get_vector(values, 0, 1);
-    let p2 = get_vector(values, 2, 3);
-    let p3 = get_vector(values, 4, 5);
-    (
-        Bezier3::new(*from, p1, p2, p3).get_appro_length(SPLIT_COUNT),
-        p1,
-        p2,
-    )
+    ret
 }
 
-fn get_length_c_relative(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2, Vector2) {
-    let p1 = get_vector(values, 0, 1) + *from;
-    let p2 = get_vector(values, 2, 3) + *from;
-    let p3 = get_vector(values, 4, 5) + *from;
-    (
-        Bezier3::new(*from, p1, p2, p3).get_appro_length(SPLIT_COUNT),
-        p1,
-        p2,
-    )
+#[derive(Debug, PartialEq)]
+pub struct SubpathSegments {
+    // Starts with an absolute moveto, so it can be used on its own
+    pub segments: Vec<PathSegment>,
+    pub start: Vector2,
+    pub closed: bool,
+    pub length: f64,
+    // Indices of the segments in the original path
+    pub range: Range<usize>,
 }
 
-fn get_length_s(values: &Vec<f64>, from: &Vector2, control: &Vector2) -> (f64, Vector2, Vector2) {
-    let p1 = from.multi(2.0) - *control;
-    let p2 = get_vector(values, 0, 1);
-    let p3 = get_vector(values, 2, 3);
-    (
-        Bezier3::new(*from, p1, p2, p3).get_appro_length(SPLIT_COUNT),
-        p1,
-        p2,
-    )
+pub fn split_subpaths(segments: &[PathSegment]) -> Vec<SubpathSegments> {
+    let subpaths = get_subpaths(segments);
+    let ends: Vec<usize> = subpaths
+        .iter()
+        .skip(1)
+        .map(|s| s.begin)
+        .chain([segments.len()])
+        .collect();
+
+    subpaths
+        .iter()
+        .zip(ends)
+        .enumerate()
+        .map(|(k, (subpath, end))| {
+            let mut list = vec![PathSegment::new(
+                'M',
+                vec![subpath.start.0, subpath.start.1],
+            )];
+            let rest = match Segment::try_from(&segments[subpath.begin]) {
+                // These open the subpath themselves, see `get_subpaths`
+                Ok(Segment::MoveTo { .. }) => subpath.begin + 1,
+                Ok(Segment::LineTo { .. }) if k == 0 => subpath.begin + 1,
+                _ => subpath.begin,
+            };
+            list.extend_from_slice(&segments[rest..end]);
+
+            SubpathSegments {
+                segments: list,
+                start: subpath.start,
+                closed: subpath.closed,
+                length: subpath
+                    .curves
+                    .iter()
+                    .map(|(_, c)| c.get_appro_length(SPLIT_COUNT))
+                    .sum(),
+                range: subpath.begin..end,
+            }
+        })
+        .collect()
 }
 
-fn get_length_s_relative(
-    values: &Vec<f64>,
-    from: &Vector2,
-    control: &Vector2,
-) -> (f64, Vector2, Vector2) {
-    let p1 = from.multi(2.0) - *control;
-    let p2 = get_vector(values, 0, 1) + *from;
-    let p3 = get_vector(values, 2, 3) + *from;
-    (
-        Bezier3::new(*from, p1, p2, p3).get_appro_length(SPLIT_COUNT),
-        p1,
-        p2,
-    )
+// A run of curves starting at a moveto, with absolute coordinates resolved.
+// `begin` is the index of the segment opening the subpath. Each curve keeps
+// the index of the segment it came from; the closing line of a closed
+// subpath refers to its 'Z' segment.
+pub(crate) struct Subpath {
+    pub begin: usize,
+    pub start: Vector2,
+    pub closed: bool,
+    pub curves: Vec<(usize, Curve)>,
 }
 
-fn get_length_a(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2) {
-    let p1 = get_vector(values, 5, 6);
-    (
-        Arc::new(
-            *from,
-            get_number(values, 0),
-            get_number(values, 1),
-            get_number(values, 2),
-            get_bool(values, 3),
-            get_bool(values, 4),
-            p1,
-        )
-        .get_appro_length(SPLIT_COUNT),
-        p1,
-    )
+pub(crate) fn get_subpaths(segments: &[PathSegment]) -> Vec<Subpath> {
+    let mut ret: Vec<Subpath> = vec![];
+    for g in iter_geometry(segments) {
+        match g.primitive {
+            Primitive::MoveTo(p) => ret.push(Subpath {
+                begin: g.index,
+                start: p,
+                closed: false,
+                curves: vec![],
+            }),
+            primitive => {
+                // Every curve follows a moveto
+                let subpath = ret.last_mut().unwrap();
+                subpath.closed = matches!(primitive, Primitive::Close(_));
+                subpath
+                    .curves
+                    .extend(primitive.to_curve().map(|c| (g.index, c)));
+            }
+        }
+    }
+    ret
 }
 
-fn get_length_a_relative(values: &Vec<f64>, from: &Vector2) -> (f64, Vector2) {
-    let p1 = get_vector(values, 5, 6) + *from;
-    (
-        Arc::new(
-            *from,
-            get_number(values, 0),
-            get_number(values, 1),
-            get_number(values, 2),
-            get_bool(values, 3),
-            get_bool(values, 4),
-            p1,
-        )
-        .get_appro_length(SPLIT_COUNT),
-        p1,
-    )
-}
+pub(crate) static SPLIT_COUNT: usize = 20;
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use super::*;
 
@@ -355,6 +256,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_path_lengths_cases() {
+        assert_eq!(
+            get_path_lengths(&["M0 0 L3 4", "M0 0 L1", "", "M0 0 h2 v2 z"]),
+            vec![
+                Ok(5.0),
+                Err(ParseError::LackOfParameter('L')),
+                Ok(0.0),
+                Ok(2.0 + 2.0 + 8.0_f64.sqrt()),
+            ]
+        );
+    }
+
     #[test]
     fn get_path_segment_length_m() {
         assert_eq!(
@@ -404,7 +318,7 @@ mod tests {
                 PathSegment::new('M', vec![1.0, 2.0]),
                 PathSegment::new('H', vec![9.0]),
             ]),
-            9.0
+            8.0
         );
 
         assert_eq!(
@@ -423,7 +337,7 @@ mod tests {
                 PathSegment::new('M', vec![1.0, 2.0]),
                 PathSegment::new('V', vec![9.0]),
             ]),
-            9.0
+            7.0
         );
 
         assert_eq!(
@@ -527,7 +441,8 @@ mod tests {
                 PathSegment::new('S', vec![30.0, 20.0, 30.0, 30.0]),
             ])
             .round(),
-            33.0
+            32.0,
+            "should not reflect the control of a quadratic curve"
         );
 
         assert_eq!(
@@ -537,7 +452,17 @@ mod tests {
                 PathSegment::new('s', vec![10.0, 0.0, 10.0, 10.0]),
             ])
             .round(),
-            33.0
+            32.0
+        );
+
+        // The second control of the previous cubic curve is reflected
+        assert_eq!(
+            get_path_length(&[
+                PathSegment::new('M', vec![0.0, 0.0]),
+                PathSegment::new('C', vec![0.0, 0.0, 10.0, 0.0, 10.0, 0.0]),
+                PathSegment::new('S', vec![20.0, 0.0, 20.0, 0.0]),
+            ]),
+            20.0
         );
     }
 
@@ -612,4 +537,160 @@ mod tests {
             47.0
         );
     }
+
+    #[test]
+    fn get_path_segment_length_a_out_of_range() {
+        assert_eq!(
+            get_path_length(&[
+                PathSegment::new('M', vec![10.0, 10.0]),
+                PathSegment::new('A', vec![10.0, 10.0, 0.0, 0.0, 0.0, 10.0, 10.0]),
+            ]),
+            0.0,
+            "should skip the arc ending at its start"
+        );
+        assert_eq!(
+            get_path_length(&[
+                PathSegment::new('M', vec![10.0, 10.0]),
+                PathSegment::new('a', vec![0.0, 10.0, 0.0, 0.0, 0.0, 3.0, 4.0]),
+            ]),
+            5.0,
+            "should treat the arc without radii as a line"
+        );
+    }
+
+    #[test]
+    fn get_path_segment_length_lack_of_parameter() {
+        assert_eq!(
+            get_path_length(&[
+                PathSegment::new('M', vec![0.0, 0.0]),
+                PathSegment::new('C', vec![1.0]),
+                PathSegment::new('L', vec![3.0, 4.0]),
+            ]),
+            5.0,
+            "should skip the segment missing values"
+        );
+    }
+
+    #[test]
+    fn split_subpaths_cases() {
+        let ret = split_subpaths(&[
+            PathSegment::new('M', vec![1.0, 1.0]),
+            PathSegment::new('l', vec![3.0, 0.0]),
+            PathSegment::new('l', vec![0.0, 3.0]),
+            PathSegment::new('z', vec![]),
+            PathSegment::new('m', vec![10.0, 10.0]),
+            PathSegment::new('h', vec![2.0]),
+            PathSegment::new('Z', vec![]),
+            PathSegment::new('l', vec![0.0, 5.0]),
+        ]);
+        assert_eq!(
+            ret,
+            vec![
+                SubpathSegments {
+                    segments: vec![
+                        PathSegment::new('M', vec![1.0, 1.0]),
+                        PathSegment::new('l', vec![3.0, 0.0]),
+                        PathSegment::new('l', vec![0.0, 3.0]),
+                        PathSegment::new('z', vec![]),
+                    ],
+                    start: Vector2(1.0, 1.0),
+                    closed: true,
+                    length: 3.0 + 3.0 + 18.0_f64.sqrt(),
+                    range: 0..4,
+                },
+                SubpathSegments {
+                    segments: vec![
+                        PathSegment::new('M', vec![11.0, 11.0]),
+                        PathSegment::new('h', vec![2.0]),
+                        PathSegment::new('Z', vec![]),
+                    ],
+                    start: Vector2(11.0, 11.0),
+                    closed: true,
+                    length: 4.0,
+                    range: 4..7,
+                },
+                SubpathSegments {
+                    segments: vec![
+                        PathSegment::new('M', vec![11.0, 11.0]),
+                        PathSegment::new('l', vec![0.0, 5.0]),
+                    ],
+                    start: Vector2(11.0, 11.0),
+                    closed: false,
+                    length: 5.0,
+                    range: 7..8,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn split_subpaths_without_moveto() {
+        let ret = split_subpaths(&[
+            PathSegment::new('L', vec![1.0, 1.0]),
+            PathSegment::new('L', vec![4.0, 5.0]),
+            PathSegment::new('M', vec![0.0, 0.0]),
+        ]);
+        assert_eq!(ret.len(), 2);
+        assert_eq!(
+            ret[0].segments,
+            vec![
+                PathSegment::new('M', vec![1.0, 1.0]),
+                PathSegment::new('L', vec![4.0, 5.0]),
+            ]
+        );
+        assert_eq!(ret[0].length, 5.0);
+        assert_eq!(ret[0].range, 0..2);
+        assert_eq!(ret[1].segments, vec![PathSegment::new('M', vec![0.0, 0.0])]);
+        assert_eq!(ret[1].length, 0.0);
+        assert_eq!(ret[1].range, 2..3);
+    }
+
+    #[test]
+    fn get_closest_point_cases() {
+        let segments = [
+            PathSegment::new('M', vec![0.0, 0.0]),
+            PathSegment::new('L', vec![10.0, 0.0]),
+            PathSegment::new('L', vec![10.0, 10.0]),
+            PathSegment::new('Z', vec![]),
+        ];
+        assert_eq!(
+            get_closest_point(&segments, Vector2(4.0, -3.0)),
+            Some(ClosestPoint {
+                point: Vector2(4.0, 0.0),
+                distance: 3.0,
+                index: 1,
+                t: 0.4,
+                length: 4.0,
+            })
+        );
+        assert_eq!(
+            get_closest_point(&segments, Vector2(12.0, 5.0)),
+            Some(ClosestPoint {
+                point: Vector2(10.0, 5.0),
+                distance: 2.0,
+                index: 2,
+                t: 0.5,
+                length: 15.0,
+            })
+        );
+        let ret = get_closest_point(&segments, Vector2(0.0, 10.0)).unwrap();
+        assert_eq!(ret.index, 3);
+        assert_eq!(ret.t, 0.5);
+        assert_eq!(ret.length, 20.0 + 50.0_f64.sqrt());
+
+        assert_eq!(get_closest_point(&[], Vector2(0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn get_closest_point_curves() {
+        let segments = [
+            PathSegment::new('M', vec![10.0, 0.0]),
+            PathSegment::new('A', vec![10.0, 10.0, 0.0, 0.0, 1.0, -10.0, 0.0]),
+        ];
+        let ret = get_closest_point(&segments, Vector2(0.0, 20.0)).unwrap();
+        assert!((ret.point - Vector2(0.0, 10.0)).norm() < 1e-9);
+        assert!((ret.distance - 10.0).abs() < 1e-9);
+        assert!((ret.t - 0.5).abs() < 1e-9);
+        assert!((ret.length - get_path_length(&segments) / 2.0).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,195 @@
+use crate::path::{get_subpaths, PathSegment, SPLIT_COUNT};
+use crate::vector::{Curve, Lerpable, Line, Vector2};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sample {
+    pub point: Vector2,
+    // Direction of the tangent in radians
+    pub angle: f64,
+}
+
+// `count` points at equal distances along the path, including both ends.
+// With `jumps`, the gaps between subpaths count as straight lines to be
+// sampled, otherwise they're skipped.
+pub fn sample_points(segments: &[PathSegment], count: usize, jumps: bool) -> Vec<Sample> {
+    let curves = get_sampled_curves(segments, jumps);
+    let total: f64 = curves.iter().map(|(_, d)| d).sum();
+    let distances = (0..count).map(|i| {
+        if count == 1 {
+            0.0
+        } else {
+            total * i as f64 / (count - 1) as f64
+        }
+    });
+    sample_at(&curves, distances)
+}
+
+// Upper bound of the points from `sample_points_every`
+pub const MAX_SAMPLE_COUNT: usize = 1 << 20;
+
+// Points every `spacing` from the start of the path, in the same way as
+// `sample_points`. Nothing is returned when the spacing is so small
+// relative to the length that it'd take more than `MAX_SAMPLE_COUNT` points.
+pub fn sample_points_every(segments: &[PathSegment], spacing: f64, jumps: bool) -> Vec<Sample> {
+    if spacing <= 0.0 || !spacing.is_finite() {
+        return vec![];
+    }
+    let curves = get_sampled_curves(segments, jumps);
+    let total: f64 = curves.iter().map(|(_, d)| d).sum();
+    let steps = (total / spacing + 1e-9).floor();
+    if steps.is_nan() || steps >= MAX_SAMPLE_COUNT as f64 {
+        return vec![];
+    }
+    sample_at(&curves, (0..=steps as usize).map(|i| spacing * i as f64))
+}
+
+// Curves with their lengths, in the order they're drawn
+fn get_sampled_curves(segments: &[PathSegment], jumps: bool) -> Vec<(Curve, f64)> {
+    let mut ret: Vec<(Curve, f64)> = vec![];
+    let mut current: Option<Vector2> = None;
+    for subpath in get_subpaths(segments) {
+        if let (true, Some(p)) = (jumps, current) {
+            if p != subpath.start {
+                let jump = Curve::Line(Line::new(p, subpath.start));
+                ret.push((jump, jump.get_appro_length(SPLIT_COUNT)));
+            }
+        }
+        for (_, curve) in &subpath.curves {
+            ret.push((*curve, curve.get_appro_length(SPLIT_COUNT)));
+        }
+        current = Some(match (subpath.closed, subpath.curves.last()) {
+            (false, Some((_, last))) => last.end(),
+            _ => subpath.start,
+        });
+    }
+
+    // Keeps a lone point sampleable
+    if ret.is_empty() {
+        if let Some(p) = current {
+            ret.push((Curve::Line(Line::new(p, p)), 0.0));
+        }
+    }
+    ret
+}
+
+// Walks the curves once for ascending `distances`
+fn sample_at(curves: &[(Curve, f64)], distances: impl Iterator<Item = f64>) -> Vec<Sample> {
+    let mut ret = vec![];
+    if curves.is_empty() {
+        return ret;
+    }
+
+    let mut k = 0;
+    let mut offset = 0.0;
+    for distance in distances {
+        // The last curve takes anything past the end
+        while k + 1 < curves.len() && offset + curves[k].1 < distance {
+            offset += curves[k].1;
+            k += 1;
+        }
+        let (curve, _) = &curves[k];
+        let t = curve.get_appro_t_at_length(distance - offset, SPLIT_COUNT);
+        ret.push(Sample {
+            point: curve.lerp(t),
+            angle: get_angle(curve, t),
+        });
+    }
+    ret
+}
+
+fn get_angle(curve: &Curve, t: f64) -> f64 {
+    let d = curve.derivative(t);
+    let d = if d.norm() > 0.0 {
+        d
+    } else if t < 0.5 {
+        curve.get_start_tangent()
+    } else {
+        curve.get_end_tangent()
+    };
+    d.1.atan2(d.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use std::f64::consts::PI;
+
+    fn points(samples: &[Sample]) -> Vec<Vector2> {
+        samples.iter().map(|s| s.point).collect()
+    }
+
+    #[test]
+    fn sample_points_cases() {
+        let d = parse("M0 0 h10 v10");
+        let ret = sample_points(&d, 5, false);
+        assert_eq!(
+            points(&ret),
+            vec![
+                Vector2(0.0, 0.0),
+                Vector2(5.0, 0.0),
+                Vector2(10.0, 0.0),
+                Vector2(10.0, 5.0),
+                Vector2(10.0, 10.0)
+            ]
+        );
+        assert_eq!(ret[1].angle, 0.0);
+        assert_eq!(ret[3].angle, PI / 2.0);
+        assert_eq!(
+            points(&sample_points(&d, 1, false)),
+            vec![Vector2(0.0, 0.0)]
+        );
+        assert_eq!(sample_points(&d, 0, false), vec![]);
+        assert_eq!(sample_points(&parse(""), 3, false), vec![]);
+        assert_eq!(
+            points(&sample_points(&parse("M5 5"), 2, false)),
+            vec![Vector2(5.0, 5.0), Vector2(5.0, 5.0)]
+        );
+
+        // Equal arc lengths on curves
+        let circle = parse("M10 0 A10 10 0 0 1 -10 0 A10 10 0 0 1 10 0");
+        let ret = sample_points(&circle, 5, false);
+        assert!((ret[1].point - Vector2(0.0, 10.0)).norm() < 1e-9);
+        assert!((ret[2].point - Vector2(-10.0, 0.0)).norm() < 1e-9);
+        assert!((ret[1].angle.abs() - PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sample_points_jumps() {
+        let d = parse("M0 0 h10 M20 0 h10");
+        assert_eq!(
+            points(&sample_points_every(&d, 5.0, false)),
+            vec![
+                Vector2(0.0, 0.0),
+                Vector2(5.0, 0.0),
+                Vector2(10.0, 0.0),
+                Vector2(25.0, 0.0),
+                Vector2(30.0, 0.0)
+            ]
+        );
+        assert_eq!(
+            points(&sample_points_every(&d, 5.0, true)),
+            vec![
+                Vector2(0.0, 0.0),
+                Vector2(5.0, 0.0),
+                Vector2(10.0, 0.0),
+                Vector2(15.0, 0.0),
+                Vector2(20.0, 0.0),
+                Vector2(25.0, 0.0),
+                Vector2(30.0, 0.0)
+            ]
+        );
+        assert_eq!(sample_points_every(&d, 7.0, false).len(), 3);
+        assert_eq!(sample_points_every(&d, 0.0, false), vec![]);
+
+        // Too many points for the length
+        assert_eq!(sample_points_every(&d, 1e-12, false), vec![]);
+        let line = parse("M0 0 h1");
+        let spacing = 1.0 / (MAX_SAMPLE_COUNT - 1) as f64;
+        assert_eq!(
+            sample_points_every(&line, spacing, false).len(),
+            MAX_SAMPLE_COUNT
+        );
+        assert_eq!(sample_points_every(&line, spacing / 2.0, false), vec![]);
+    }
+}
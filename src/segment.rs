@@ -0,0 +1,255 @@
+use std::convert::TryFrom;
+
+use crate::parser::ParseError;
+use crate::path::PathSegment;
+use crate::vector::Vector2;
+
+// Typed counterpart of `PathSegment`. `abs` is false for the relative
+// (lowercase) commands, whose points are offsets from the current point.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Segment {
+    MoveTo {
+        abs: bool,
+        to: Vector2,
+    },
+    LineTo {
+        abs: bool,
+        to: Vector2,
+    },
+    HorizontalTo {
+        abs: bool,
+        x: f64,
+    },
+    VerticalTo {
+        abs: bool,
+        y: f64,
+    },
+    QuadTo {
+        abs: bool,
+        c: Vector2,
+        to: Vector2,
+    },
+    SmoothQuadTo {
+        abs: bool,
+        to: Vector2,
+    },
+    CubicTo {
+        abs: bool,
+        c1: Vector2,
+        c2: Vector2,
+        to: Vector2,
+    },
+    SmoothCubicTo {
+        abs: bool,
+        c2: Vector2,
+        to: Vector2,
+    },
+    ArcTo {
+        abs: bool,
+        rx: f64,
+        ry: f64,
+        // Degrees
+        rotation: f64,
+        large: bool,
+        sweep: bool,
+        to: Vector2,
+    },
+    Close {
+        abs: bool,
+    },
+}
+
+impl Segment {
+    // Same as converting `PathSegment::new(command, values)`
+    pub fn from_values(command: char, values: &[f64]) -> Result<Self, ParseError> {
+        let abs = command.is_ascii_uppercase();
+        let count = get_param_count(command).ok_or(ParseError::UnexpectedCommand(command))?;
+        let v = values;
+        if v.len() < count {
+            return Err(ParseError::LackOfParameter(command));
+        }
+        let p = |i: usize| Vector2(v[i], v[i + 1]);
+
+        Ok(match command.to_ascii_uppercase() {
+            'M' => Segment::MoveTo { abs, to: p(0) },
+            'L' => Segment::LineTo { abs, to: p(0) },
+            'H' => Segment::HorizontalTo { abs, x: v[0] },
+            'V' => Segment::VerticalTo { abs, y: v[0] },
+            'Q' => Segment::QuadTo {
+                abs,
+                c: p(0),
+                to: p(2),
+            },
+            'T' => Segment::SmoothQuadTo { abs, to: p(0) },
+            'C' => Segment::CubicTo {
+                abs,
+                c1: p(0),
+                c2: p(2),
+                to: p(4),
+            },
+            'S' => Segment::SmoothCubicTo {
+                abs,
+                c2: p(0),
+                to: p(2),
+            },
+            'A' => Segment::ArcTo {
+                abs,
+                rx: v[0],
+                ry: v[1],
+                rotation: v[2],
+                large: v[3] != 0.0,
+                sweep: v[4] != 0.0,
+                to: p(5),
+            },
+            _ => Segment::Close { abs },
+        })
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        match *self {
+            Segment::MoveTo { abs, .. }
+            | Segment::LineTo { abs, .. }
+            | Segment::HorizontalTo { abs, .. }
+            | Segment::VerticalTo { abs, .. }
+            | Segment::QuadTo { abs, .. }
+            | Segment::SmoothQuadTo { abs, .. }
+            | Segment::CubicTo { abs, .. }
+            | Segment::SmoothCubicTo { abs, .. }
+            | Segment::ArcTo { abs, .. }
+            | Segment::Close { abs } => abs,
+        }
+    }
+
+    // The command letter, lowercase for relative ones
+    pub fn command(&self) -> char {
+        let c = match self {
+            Segment::MoveTo { .. } => 'M',
+            Segment::LineTo { .. } => 'L',
+            Segment::HorizontalTo { .. } => 'H',
+            Segment::VerticalTo { .. } => 'V',
+            Segment::QuadTo { .. } => 'Q',
+            Segment::SmoothQuadTo { .. } => 'T',
+            Segment::CubicTo { .. } => 'C',
+            Segment::SmoothCubicTo { .. } => 'S',
+            Segment::ArcTo { .. } => 'A',
+            Segment::Close { .. } => 'Z',
+        };
+        if self.is_absolute() {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    }
+}
+
+// Fails for unknown commands and lacking values. Extra values are ignored.
+impl TryFrom<&PathSegment> for Segment {
+    type Error = ParseError;
+
+    fn try_from(seg: &PathSegment) -> Result<Self, Self::Error> {
+        Segment::from_values(seg._type, &seg.values)
+    }
+}
+
+// Number of values the command takes, `None` for unknown ones
+pub(crate) fn get_param_count(command: char) -> Option<usize> {
+    match command.to_ascii_uppercase() {
+        'Z' => Some(0),
+        'H' | 'V' => Some(1),
+        'M' | 'L' | 'T' => Some(2),
+        'Q' | 'S' => Some(4),
+        'C' => Some(6),
+        'A' => Some(7),
+        _ => None,
+    }
+}
+
+impl From<&Segment> for PathSegment {
+    fn from(seg: &Segment) -> Self {
+        let flag = |b: bool| if b { 1.0 } else { 0.0 };
+        let values = match *seg {
+            Segment::MoveTo { to, .. }
+            | Segment::LineTo { to, .. }
+            | Segment::SmoothQuadTo { to, .. } => vec![to.0, to.1],
+            Segment::HorizontalTo { x, .. } => vec![x],
+            Segment::VerticalTo { y, .. } => vec![y],
+            Segment::QuadTo { c, to, .. } => vec![c.0, c.1, to.0, to.1],
+            Segment::CubicTo { c1, c2, to, .. } => vec![c1.0, c1.1, c2.0, c2.1, to.0, to.1],
+            Segment::SmoothCubicTo { c2, to, .. } => vec![c2.0, c2.1, to.0, to.1],
+            Segment::ArcTo {
+                rx,
+                ry,
+                rotation,
+                large,
+                sweep,
+                to,
+                ..
+            } => vec![rx, ry, rotation, flag(large), flag(sweep), to.0, to.1],
+            Segment::Close { .. } => vec![],
+        };
+        PathSegment::new(seg.command(), values)
+    }
+}
+
+// Typed segments, or the error of the first invalid one
+pub fn from_path_segments(segments: &[PathSegment]) -> Result<Vec<Segment>, ParseError> {
+    segments.iter().map(Segment::try_from).collect()
+}
+
+pub fn to_path_segments(segments: &[Segment]) -> Vec<PathSegment> {
+    segments.iter().map(PathSegment::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn segment_conversion_cases() {
+        let d = parse("M1 2 L3 4 H5 V6 Q1 2 3 4 T5 6 C1 2 3 4 5 6 S1 2 3 4 A1 2 30 1 0 3 4 Z");
+        let typed = from_path_segments(&d).unwrap();
+        assert_eq!(to_path_segments(&typed), d);
+        assert_eq!(
+            typed[6],
+            Segment::CubicTo {
+                abs: true,
+                c1: Vector2(1.0, 2.0),
+                c2: Vector2(3.0, 4.0),
+                to: Vector2(5.0, 6.0),
+            }
+        );
+        assert_eq!(
+            typed[8],
+            Segment::ArcTo {
+                abs: true,
+                rx: 1.0,
+                ry: 2.0,
+                rotation: 30.0,
+                large: true,
+                sweep: false,
+                to: Vector2(3.0, 4.0),
+            }
+        );
+
+        let d = parse("m1 2 l3 4 h5 v6 q1 2 3 4 t5 6 c1 2 3 4 5 6 s1 2 3 4 a1 2 30 0 1 3 4 z");
+        let typed = from_path_segments(&d).unwrap();
+        assert!(typed.iter().all(|s| !s.is_absolute()));
+        assert_eq!(to_path_segments(&typed), d);
+    }
+
+    #[test]
+    fn segment_conversion_errors() {
+        assert_eq!(
+            Segment::try_from(&PathSegment::new('C', vec![1.0, 2.0])),
+            Err(ParseError::LackOfParameter('C'))
+        );
+        assert_eq!(
+            from_path_segments(&[
+                PathSegment::new('M', vec![0.0, 0.0]),
+                PathSegment::new('K', vec![]),
+            ]),
+            Err(ParseError::UnexpectedCommand('K'))
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use crate::path::PathSegment;
+
+pub fn serialize(segments: &[PathSegment]) -> String {
+    segments
+        .iter()
+        .map(serialize_segment)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn serialize_segment(seg: &PathSegment) -> String {
+    let mut ret = String::from(seg._type);
+    let values: Vec<String> = seg.values.iter().map(|v| v.to_string()).collect();
+    ret.push_str(&values.join(" "));
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_cases() {
+        assert_eq!(serialize(&[]), "");
+        assert_eq!(
+            serialize(&[
+                PathSegment::new('M', vec![1.0, -2.0]),
+                PathSegment::new('l', vec![0.5, 10.0]),
+                PathSegment::new('z', vec![]),
+            ]),
+            "M1 -2 l0.5 10 z"
+        );
+    }
+}
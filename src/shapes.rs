@@ -0,0 +1,152 @@
+use crate::path::PathSegment;
+use crate::vector::Vector2;
+
+// Equivalent paths of the SVG 2 basic shapes. Shapes disabled by their sizes
+// have no segments.
+// https://www.w3.org/TR/SVG2/shapes.html
+
+// `None` or negative corner radii are "auto" and take the other one
+pub fn rect(
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    rx: Option<f64>,
+    ry: Option<f64>,
+) -> Vec<PathSegment> {
+    if width <= 0.0 || height <= 0.0 {
+        return vec![];
+    }
+
+    let (rx, ry) = get_radii(rx, ry);
+    let rx = rx.unwrap_or(0.0).min(width / 2.0);
+    let ry = ry.unwrap_or(0.0).min(height / 2.0);
+    let rounded = rx > 0.0 && ry > 0.0;
+    let corner = |ret: &mut Vec<PathSegment>, px: f64, py: f64| {
+        if rounded {
+            ret.push(PathSegment::new('A', vec![rx, ry, 0.0, 0.0, 1.0, px, py]));
+        }
+    };
+
+    let mut ret = vec![
+        PathSegment::new('M', vec![x + rx, y]),
+        PathSegment::new('H', vec![x + width - rx]),
+    ];
+    corner(&mut ret, x + width, y + ry);
+    ret.push(PathSegment::new('V', vec![y + height - ry]));
+    corner(&mut ret, x + width - rx, y + height);
+    ret.push(PathSegment::new('H', vec![x + rx]));
+    corner(&mut ret, x, y + height - ry);
+    ret.push(PathSegment::new('V', vec![y + ry]));
+    corner(&mut ret, x + rx, y);
+    ret.push(PathSegment::new('Z', vec![]));
+    ret
+}
+
+pub fn circle(cx: f64, cy: f64, r: f64) -> Vec<PathSegment> {
+    ellipse(cx, cy, Some(r), Some(r))
+}
+
+// `None` or negative radii are "auto" and take the other one
+pub fn ellipse(cx: f64, cy: f64, rx: Option<f64>, ry: Option<f64>) -> Vec<PathSegment> {
+    let (rx, ry) = match get_radii(rx, ry) {
+        (Some(rx), Some(ry)) if rx > 0.0 && ry > 0.0 => (rx, ry),
+        _ => return vec![],
+    };
+
+    // Clockwise on screen from the rightmost point
+    let arc = |px: f64, py: f64| PathSegment::new('A', vec![rx, ry, 0.0, 0.0, 1.0, px, py]);
+    vec![
+        PathSegment::new('M', vec![cx + rx, cy]),
+        arc(cx, cy + ry),
+        arc(cx - rx, cy),
+        arc(cx, cy - ry),
+        arc(cx + rx, cy),
+        PathSegment::new('Z', vec![]),
+    ]
+}
+
+pub fn line(x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<PathSegment> {
+    vec![
+        PathSegment::new('M', vec![x1, y1]),
+        PathSegment::new('L', vec![x2, y2]),
+    ]
+}
+
+pub fn polyline(points: &[Vector2]) -> Vec<PathSegment> {
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| PathSegment::new(if i == 0 { 'M' } else { 'L' }, vec![p.0, p.1]))
+        .collect()
+}
+
+pub fn polygon(points: &[Vector2]) -> Vec<PathSegment> {
+    let mut ret = polyline(points);
+    if !ret.is_empty() {
+        ret.push(PathSegment::new('Z', vec![]));
+    }
+    ret
+}
+
+fn get_radii(rx: Option<f64>, ry: Option<f64>) -> (Option<f64>, Option<f64>) {
+    let rx = rx.filter(|v| *v >= 0.0);
+    let ry = ry.filter(|v| *v >= 0.0);
+    (rx.or(ry), ry.or(rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::get_path_length;
+    use crate::serializer::serialize;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn rect_cases() {
+        assert_eq!(
+            serialize(&rect(1.0, 2.0, 10.0, 20.0, None, None)),
+            "M1 2 H11 V22 H1 V2 Z"
+        );
+        assert_eq!(
+            serialize(&rect(0.0, 0.0, 10.0, 20.0, Some(2.0), None)),
+            "M2 0 H8 A2 2 0 0 1 10 2 V18 A2 2 0 0 1 8 20 H2 A2 2 0 0 1 0 18 V2 A2 2 0 0 1 2 0 Z"
+        );
+        // Radii are clamped to the half of the sides
+        assert_eq!(
+            serialize(&rect(0.0, 0.0, 10.0, 20.0, Some(-1.0), Some(30.0))),
+            "M5 0 H5 A5 10 0 0 1 10 10 V10 A5 10 0 0 1 5 20 H5 A5 10 0 0 1 0 10 V10 A5 10 0 0 1 5 0 Z"
+        );
+        // No rounding when either is zero
+        assert_eq!(
+            serialize(&rect(0.0, 0.0, 10.0, 20.0, Some(0.0), Some(3.0))),
+            "M0 0 H10 V17 H0 V3 Z"
+        );
+        assert_eq!(rect(0.0, 0.0, 0.0, 20.0, None, None), vec![]);
+    }
+
+    #[test]
+    fn ellipse_cases() {
+        assert_eq!(
+            serialize(&circle(1.0, 2.0, 3.0)),
+            "M4 2 A3 3 0 0 1 1 5 A3 3 0 0 1 -2 2 A3 3 0 0 1 1 -1 A3 3 0 0 1 4 2 Z"
+        );
+        assert!((get_path_length(&circle(0.0, 0.0, 10.0)) - 20.0 * PI).abs() < 0.1);
+        assert_eq!(
+            serialize(&ellipse(0.0, 0.0, None, Some(2.0))),
+            serialize(&circle(0.0, 0.0, 2.0))
+        );
+        assert_eq!(circle(0.0, 0.0, 0.0), vec![]);
+        assert_eq!(ellipse(0.0, 0.0, Some(1.0), Some(0.0)), vec![]);
+        assert_eq!(ellipse(0.0, 0.0, None, None), vec![]);
+    }
+
+    #[test]
+    fn polyline_cases() {
+        assert_eq!(serialize(&line(1.0, 2.0, 3.0, 4.0)), "M1 2 L3 4");
+        let points = [Vector2(0.0, 0.0), Vector2(1.0, 0.0), Vector2(1.0, 1.0)];
+        assert_eq!(serialize(&polyline(&points)), "M0 0 L1 0 L1 1");
+        assert_eq!(serialize(&polygon(&points)), "M0 0 L1 0 L1 1 Z");
+        assert_eq!(polygon(&[]), vec![]);
+    }
+}
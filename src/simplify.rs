@@ -0,0 +1,297 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::keyword::Keyword;
+use crate::path::{get_subpaths, PathSegment};
+use crate::vector::{Curve, Vector2};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SimplifyMethod {
+    // Keeps the farthest point from the chord while it's out of tolerance
+    RamerDouglasPeucker,
+    // Drops the point making the smallest triangle with its neighbors while
+    // the area is within the square of the tolerance
+    Visvalingam,
+}
+
+impl Keyword for SimplifyMethod {
+    const KEYWORDS: &'static [(&'static str, Self)] = &[
+        ("rdp", SimplifyMethod::RamerDouglasPeucker),
+        ("visvalingam", SimplifyMethod::Visvalingam),
+    ];
+}
+
+// Simplifies runs of straight lines in the path. Curves, the start points
+// of subpaths and the ends of each run stay as they are. The closing line of
+// a closed subpath belongs to the run before it, and 'Z' still closes it.
+pub fn simplify(
+    segments: &[PathSegment],
+    tolerance: f64,
+    method: SimplifyMethod,
+) -> Vec<PathSegment> {
+    let mut ret: Vec<PathSegment> = vec![];
+    for subpath in get_subpaths(segments) {
+        ret.push(PathSegment::new(
+            'M',
+            vec![subpath.start.0, subpath.start.1],
+        ));
+
+        let mut run: Vec<Vector2> = vec![subpath.start];
+        let flush = |run: &mut Vec<Vector2>, ret: &mut Vec<PathSegment>| {
+            for p in simplify_polyline(run, tolerance, method).iter().skip(1) {
+                ret.push(PathSegment::new('L', vec![p.0, p.1]));
+            }
+            let last = run[run.len() - 1];
+            run.clear();
+            run.push(last);
+        };
+
+        for (index, curve) in &subpath.curves {
+            let straight = matches!(curve, Curve::Line(_))
+                && matches!(
+                    segments[*index]._type,
+                    'L' | 'l' | 'H' | 'h' | 'V' | 'v' | 'Z' | 'z'
+                );
+            if straight {
+                run.push(curve.end());
+            } else {
+                flush(&mut run, &mut ret);
+                ret.push(PathSegment::from_curve(curve));
+                run[0] = curve.end();
+            }
+        }
+        flush(&mut run, &mut ret);
+
+        if subpath.closed {
+            // 'Z' draws the line back to the start
+            if let Some(last) = ret.last() {
+                if last._type == 'L' && last.values == [subpath.start.0, subpath.start.1] {
+                    ret.pop();
+                }
+            }
+            ret.push(PathSegment::new('Z', vec![]));
+        }
+    }
+    ret
+}
+
+// Simplified polyline keeping both ends
+pub fn simplify_polyline(
+    points: &[Vector2],
+    tolerance: f64,
+    method: SimplifyMethod,
+) -> Vec<Vector2> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
+
+    let keep = match method {
+        SimplifyMethod::RamerDouglasPeucker => {
+            let mut keep = vec![false; points.len()];
+            keep[0] = true;
+            keep[points.len() - 1] = true;
+            mark_rdp(points, tolerance, 0, points.len() - 1, &mut keep);
+            keep
+        }
+        SimplifyMethod::Visvalingam => get_visvalingam(points, tolerance * tolerance),
+    };
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, k)| *k)
+        .map(|(p, _)| *p)
+        .collect()
+}
+
+fn get_distance_to_segment(p: Vector2, a: Vector2, b: Vector2) -> f64 {
+    let d = b - a;
+    let dd = d.dot(d);
+    if dd == 0.0 {
+        return (p - a).norm();
+    }
+    let t = ((p - a).dot(d) / dd).clamp(0.0, 1.0);
+    (p - (a + d.multi(t))).norm()
+}
+
+fn mark_rdp(points: &[Vector2], tolerance: f64, first: usize, last: usize, keep: &mut [bool]) {
+    let (farthest, distance) = ((first + 1)..last)
+        .map(|i| {
+            (
+                i,
+                get_distance_to_segment(points[i], points[first], points[last]),
+            )
+        })
+        .fold((first, 0.0), |acc, v| if v.1 > acc.1 { v } else { acc });
+    if distance > tolerance {
+        keep[farthest] = true;
+        mark_rdp(points, tolerance, first, farthest, keep);
+        mark_rdp(points, tolerance, farthest, last, keep);
+    }
+}
+
+fn get_visvalingam(points: &[Vector2], threshold: f64) -> Vec<bool> {
+    let n = points.len();
+    let mut keep = vec![true; n];
+    let mut prev: Vec<usize> = (0..n).map(|i| i.saturating_sub(1)).collect();
+    let mut next: Vec<usize> = (0..n).map(|i| (i + 1).min(n - 1)).collect();
+    let area = |i: usize, prev: &[usize], next: &[usize]| {
+        let (a, b) = (points[prev[i]], points[next[i]]);
+        (b - a).cross(points[i] - a).abs() / 2.0
+    };
+
+    let mut areas: Vec<f64> = (0..n).map(|i| area(i, &prev, &next)).collect();
+    let mut heap: BinaryHeap<Candidate> = (1..(n - 1))
+        .map(|index| Candidate {
+            area: areas[index],
+            index,
+        })
+        .collect();
+    while let Some(Candidate { area: a, index: i }) = heap.pop() {
+        // Removed points and outdated areas are left in the heap
+        if !keep[i] || a.to_bits() != areas[i].to_bits() {
+            continue;
+        }
+        if a.is_nan() || a > threshold {
+            break;
+        }
+
+        keep[i] = false;
+        let (p, q) = (prev[i], next[i]);
+        next[p] = q;
+        prev[q] = p;
+        // An area never drops below the removed one, so that points are
+        // removed in the order of their effective areas
+        for j in [p, q] {
+            if 0 < j && j < n - 1 {
+                areas[j] = area(j, &prev, &next).max(a);
+                heap.push(Candidate {
+                    area: areas[j],
+                    index: j,
+                });
+            }
+        }
+    }
+    keep
+}
+
+// Ordered so that the heap pops the smallest area first, and the first
+// point among equal ones. NaN comes after every number.
+struct Candidate {
+    area: f64,
+    index: usize,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .area
+            .total_cmp(&self.area)
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::serializer::serialize;
+
+    fn run(d: &str, tolerance: f64, method: SimplifyMethod) -> String {
+        serialize(&simplify(&parse(d), tolerance, method))
+    }
+
+    #[test]
+    fn simplify_method_parse_cases() {
+        assert_eq!(
+            SimplifyMethod::parse("rdp"),
+            Some(SimplifyMethod::RamerDouglasPeucker)
+        );
+        assert_eq!(SimplifyMethod::parse("rdp!"), None);
+    }
+
+    #[test]
+    fn simplify_polyline_cases() {
+        let points = [
+            Vector2(0.0, 0.0),
+            Vector2(1.0, 0.1),
+            Vector2(2.0, -0.1),
+            Vector2(3.0, 5.0),
+            Vector2(4.0, 6.0),
+            Vector2(5.0, 7.0),
+        ];
+        for method in [
+            SimplifyMethod::RamerDouglasPeucker,
+            SimplifyMethod::Visvalingam,
+        ] {
+            assert_eq!(
+                simplify_polyline(&points, 0.5, method),
+                vec![points[0], points[2], points[3], points[5]],
+                "{:?}",
+                method
+            );
+            // Only collinear points go away without tolerance
+            assert_eq!(
+                simplify_polyline(&points, 0.0, method),
+                vec![points[0], points[1], points[2], points[3], points[5]]
+            );
+            assert_eq!(
+                simplify_polyline(&points, 100.0, method),
+                vec![points[0], points[5]]
+            );
+        }
+    }
+
+    #[test]
+    fn simplify_polyline_non_finite() {
+        // Areas around an infinite point are NaN and never small enough
+        let points = [
+            Vector2(0.0, 0.0),
+            Vector2(1.0, 0.0),
+            Vector2(f64::INFINITY, 0.0),
+            Vector2(3.0, 0.0),
+            Vector2(4.0, 0.0),
+        ];
+        assert_eq!(
+            simplify_polyline(&points, 100.0, SimplifyMethod::Visvalingam),
+            vec![points[0], points[1], points[2], points[3], points[4]]
+        );
+    }
+
+    #[test]
+    fn simplify_paths() {
+        let rdp = SimplifyMethod::RamerDouglasPeucker;
+        assert_eq!(
+            run("M0 0 l1 0.1 l1 -0.1 l1 0 L10 10", 0.5, rdp),
+            "M0 0 L3 0 L10 10"
+        );
+        assert_eq!(
+            run("M0 0 h1 h1 Q5 5 10 0 h1 h1 v1", 0.5, rdp),
+            "M0 0 L2 0 Q5 5 10 0 L12 0 L12 1"
+        );
+
+        // The start of closed subpaths is kept
+        assert_eq!(
+            run("M5 0 h5 v5 v5 h-10 v-10 z", 0.5, rdp),
+            "M5 0 L10 0 L10 10 L0 10 L0 0 Z"
+        );
+        assert_eq!(
+            run("M0 0 h10 v10 z", 0.5, SimplifyMethod::Visvalingam),
+            "M0 0 L10 0 L10 10 Z"
+        );
+        assert_eq!(run("M0 0 h10 M5 5", 0.5, rdp), "M0 0 L10 0 M5 5");
+    }
+}
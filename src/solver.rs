@@ -0,0 +1,151 @@
+// Real roots of a*x^2 + b*x + c, falling back to the linear case
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return vec![];
+        }
+        return vec![-c / b];
+    }
+
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return vec![];
+    }
+    if disc == 0.0 {
+        return vec![-b / (2.0 * a)];
+    }
+
+    // Avoids cancellation between -b and the root of the discriminant
+    let q = -0.5 * (b + b.signum() * disc.sqrt());
+    if q == 0.0 {
+        return vec![0.0];
+    }
+    vec![q / a, c / q]
+}
+
+// Finds `t` in [t0, t1] where `f(t) == 0` by bisection.
+// `f` has to change its sign over the range.
+pub fn bisect<F: Fn(f64) -> f64>(f: F, t0: f64, t1: f64) -> f64 {
+    let mut lo = t0;
+    let mut hi = t1;
+    let positive = f(hi) > f(lo);
+
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if mid <= lo || hi <= mid {
+            break;
+        }
+        let v = f(mid);
+        if v == 0.0 {
+            return mid;
+        }
+        if (v > 0.0) == positive {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+// Real roots in [lo, hi] of the polynomial with ascending `coeffs`.
+// The roots of the derivative split the range into monotonic parts, each of
+// which has at most one root.
+pub fn solve_polynomial(coeffs: &[f64], lo: f64, hi: f64) -> Vec<f64> {
+    let mut coeffs = coeffs.to_vec();
+    while coeffs.len() > 1 && *coeffs.last().unwrap() == 0.0 {
+        coeffs.pop();
+    }
+
+    let mut ret: Vec<f64> = match coeffs.len() {
+        0 | 1 => vec![],
+        2 => vec![-coeffs[0] / coeffs[1]],
+        3 => solve_quadratic(coeffs[2], coeffs[1], coeffs[0]),
+        _ => {
+            let derivative: Vec<f64> = coeffs
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| c * i as f64)
+                .collect();
+            let f = |t: f64| eval_polynomial(&coeffs, t);
+            let scale = coeffs.iter().map(|c| c.abs()).fold(0.0, f64::max);
+
+            let mut bounds = vec![lo];
+            bounds.extend(solve_polynomial(&derivative, lo, hi));
+            bounds.push(hi);
+
+            // Also catches double roots at turning points
+            let is_zero = |v: f64| v.abs() <= scale * 1e-12;
+
+            let mut ret = vec![];
+            for w in bounds.windows(2) {
+                let (v0, v1) = (f(w[0]), f(w[1]));
+                if is_zero(v0) {
+                    ret.push(w[0]);
+                } else if (v0 < 0.0) != (v1 < 0.0) && !is_zero(v1) {
+                    ret.push(bisect(f, w[0], w[1]));
+                }
+            }
+            if is_zero(f(hi)) {
+                ret.push(hi);
+            }
+            ret
+        }
+    };
+
+    ret.retain(|t| lo <= *t && *t <= hi);
+    ret.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    ret.dedup();
+    ret
+}
+
+pub fn eval_polynomial(coeffs: &[f64], t: f64) -> f64 {
+    coeffs.iter().rev().fold(0.0, |acc, c| acc * t + c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut v: Vec<f64>) -> Vec<f64> {
+        v.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        v
+    }
+
+    #[test]
+    fn solve_quadratic_cases() {
+        assert_eq!(sorted(solve_quadratic(1.0, -3.0, 2.0)), vec![1.0, 2.0]);
+        assert_eq!(solve_quadratic(1.0, -2.0, 1.0), vec![1.0]);
+        assert_eq!(solve_quadratic(1.0, 0.0, 1.0), vec![]);
+        assert_eq!(solve_quadratic(0.0, 2.0, -1.0), vec![0.5]);
+        assert_eq!(solve_quadratic(0.0, 0.0, 1.0), vec![]);
+        assert_eq!(sorted(solve_quadratic(1.0, -1.0, 0.0)), vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn bisect_cases() {
+        assert!((bisect(|t| t * t - 2.0, 0.0, 2.0) - 2.0_f64.sqrt()).abs() < 1e-12);
+        assert!((bisect(|t| 1.0 - t, 0.0, 2.0) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn solve_polynomial_cases() {
+        // (t - 0.2)(t - 0.5)(t - 0.9)
+        let coeffs = [-0.09, 0.73, -1.6, 1.0];
+        let ret = solve_polynomial(&coeffs, 0.0, 1.0);
+        assert_eq!(ret.len(), 3);
+        for (r, e) in ret.iter().zip([0.2, 0.5, 0.9]) {
+            assert!((r - e).abs() < 1e-12);
+        }
+        assert_eq!(solve_polynomial(&coeffs, 0.3, 0.8).len(), 1);
+
+        // (t - 0.5)^2 (t - 2)
+        let ret = solve_polynomial(&[-0.5, 2.25, -3.0, 1.0], 0.0, 1.0);
+        assert_eq!(ret.len(), 1);
+        assert!((ret[0] - 0.5).abs() < 1e-6);
+
+        assert_eq!(solve_polynomial(&[1.0, 0.0, 0.0, 0.0], 0.0, 1.0), vec![]);
+        assert_eq!(solve_polynomial(&[-0.5, 1.0, 0.0], 0.0, 1.0), vec![0.5]);
+    }
+}
@@ -0,0 +1,515 @@
+use crate::boolean::get_region_outline;
+use crate::fill::{get_curve_winding_number, get_winding_number};
+use crate::keyword::{Keyword, Property};
+use crate::outline::{get_cap, get_join, get_side};
+use crate::path::{from_closed_curves, get_subpaths, PathSegment};
+use crate::vector::{Curve, Lerpable, Line, Vector2};
+
+// https://www.w3.org/TR/SVG2/painting.html#LineJoin
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+    MiterClip,
+    Arcs,
+}
+
+impl Keyword for LineJoin {
+    const KEYWORDS: &'static [(&'static str, Self)] = &[
+        ("miter", LineJoin::Miter),
+        ("round", LineJoin::Round),
+        ("bevel", LineJoin::Bevel),
+        ("miter-clip", LineJoin::MiterClip),
+        ("arcs", LineJoin::Arcs),
+    ];
+}
+
+impl Property for LineJoin {
+    const DEFAULT: Self = LineJoin::Miter;
+}
+
+// https://www.w3.org/TR/SVG2/painting.html#LineCaps
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+impl Keyword for LineCap {
+    const KEYWORDS: &'static [(&'static str, Self)] = &[
+        ("butt", LineCap::Butt),
+        ("round", LineCap::Round),
+        ("square", LineCap::Square),
+    ];
+}
+
+impl Property for LineCap {
+    const DEFAULT: Self = LineCap::Butt;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f64,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    pub miter_limit: f64,
+}
+
+impl Default for StrokeStyle {
+    // Initial values of the properties
+    fn default() -> Self {
+        StrokeStyle {
+            width: 1.0,
+            join: LineJoin::DEFAULT,
+            cap: LineCap::DEFAULT,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+// https://www.w3.org/TR/SVG2/painting.html#StrokeShape
+pub fn is_point_in_stroke(segments: &[PathSegment], p: Vector2, style: &StrokeStyle) -> bool {
+    let hw = style.width / 2.0;
+    if hw <= 0.0 {
+        return false;
+    }
+
+    get_subpaths(segments).iter().any(|subpath| {
+        if subpath.curves.is_empty() {
+            // A lone moveto isn't stroked
+            return false;
+        }
+
+        let curves: Vec<&Curve> = subpath
+            .curves
+            .iter()
+            .map(|(_, c)| c)
+            .filter(|c| c.get_start_tangent() != Vector2(0.0, 0.0))
+            .collect();
+
+        if curves.is_empty() {
+            // Zero length subpaths get caps facing +x
+            let e = subpath.start;
+            return is_in_cap(p, e, Vector2(1.0, 0.0), hw, style.cap)
+                || is_in_cap(p, e, Vector2(-1.0, 0.0), hw, style.cap);
+        }
+
+        if curves.iter().any(|c| is_in_body(p, c, hw)) {
+            return true;
+        }
+
+        for w in curves.windows(2) {
+            if is_in_join(p, w[0], w[1], hw, style) {
+                return true;
+            }
+        }
+
+        if subpath.closed {
+            is_in_join(p, curves[curves.len() - 1], curves[0], hw, style)
+        } else {
+            let first = curves[0];
+            let last = curves[curves.len() - 1];
+            is_in_cap(
+                p,
+                first.start(),
+                first.get_start_tangent().multi(-1.0),
+                hw,
+                style.cap,
+            ) || is_in_cap(p, last.end(), last.get_end_tangent(), hw, style.cap)
+        }
+    })
+}
+
+// Fill outline of the stroke. The offsets of each side of a closed subpath
+// and the loop around an open one overlap at joins and tight curves, so only
+// the outline of their nonzero fill is kept. Curves other than lines and
+// circular arcs are offset within `tolerance`.
+pub fn get_stroke_outline(
+    segments: &[PathSegment],
+    style: &StrokeStyle,
+    tolerance: f64,
+) -> Vec<PathSegment> {
+    // Negative offsets put the outline clockwise on screen
+    let dist = -style.width / 2.0;
+    if dist >= 0.0 {
+        return vec![];
+    }
+
+    let mut loops: Vec<Vec<Curve>> = vec![];
+    for subpath in get_subpaths(segments) {
+        if subpath.curves.is_empty() {
+            continue;
+        }
+
+        let curves: Vec<Curve> = subpath
+            .curves
+            .iter()
+            .map(|(_, c)| *c)
+            .filter(|c| c.get_start_tangent() != Vector2(0.0, 0.0))
+            .collect();
+        let reversed: Vec<Curve> = curves.iter().rev().map(|c| c.reverse()).collect();
+
+        if curves.is_empty() {
+            if style.cap != LineCap::Butt {
+                let e = subpath.start;
+                let mut outline = get_cap(e, Vector2(1.0, 0.0), dist, style.cap);
+                outline.extend(get_cap(e, Vector2(-1.0, 0.0), dist, style.cap));
+                loops.push(outline);
+            }
+        } else if subpath.closed {
+            loops.push(get_side(
+                &curves,
+                dist,
+                style.join,
+                style.miter_limit,
+                tolerance,
+                true,
+            ));
+            loops.push(get_side(
+                &reversed,
+                dist,
+                style.join,
+                style.miter_limit,
+                tolerance,
+                true,
+            ));
+        } else {
+            let first = curves[0];
+            let last = curves[curves.len() - 1];
+            let mut outline = get_side(
+                &curves,
+                dist,
+                style.join,
+                style.miter_limit,
+                tolerance,
+                false,
+            );
+            outline.extend(get_cap(last.end(), last.get_end_tangent(), dist, style.cap));
+            outline.extend(get_side(
+                &reversed,
+                dist,
+                style.join,
+                style.miter_limit,
+                tolerance,
+                false,
+            ));
+            outline.extend(get_cap(
+                first.start(),
+                first.get_start_tangent().multi(-1.0),
+                dist,
+                style.cap,
+            ));
+            loops.push(outline);
+        }
+    }
+
+    let outline: Vec<PathSegment> = loops.iter().flat_map(|c| from_closed_curves(c)).collect();
+    get_region_outline(&loops.concat(), |p| get_winding_number(&outline, p) != 0)
+}
+
+// Whether `p` is on a normal of the curve within the half width
+fn is_in_body(p: Vector2, curve: &Curve, hw: f64) -> bool {
+    curve
+        .get_normal_foot_t(p)
+        .iter()
+        .any(|t| (curve.lerp(*t) - p).norm() <= hw)
+}
+
+// `dir` points away from the stroke
+fn is_in_cap(p: Vector2, e: Vector2, dir: Vector2, hw: f64, cap: LineCap) -> bool {
+    match cap {
+        LineCap::Butt => false,
+        LineCap::Round => (p - e).norm() <= hw,
+        LineCap::Square => {
+            let u = dir.multi(1.0 / dir.norm());
+            let n = Vector2(-u.1, u.0);
+            let d = (p - e).dot(u);
+            0.0 <= d && d <= hw && (p - e).dot(n).abs() <= hw
+        }
+    }
+}
+
+fn is_in_join(p: Vector2, from: &Curve, to: &Curve, hw: f64, style: &StrokeStyle) -> bool {
+    let v = from.end();
+    if style.join == LineJoin::Round {
+        return (p - v).norm() <= hw;
+    }
+
+    // The region between the vertex and the join on either side
+    [hw, -hw].iter().any(|dist| {
+        let join = get_join(from, to, *dist, style.join, style.miter_limit);
+        let (a, b) = match (join.first(), join.last()) {
+            (Some(a), Some(b)) => (a.start(), b.end()),
+            _ => return false,
+        };
+        let winding: i32 = join
+            .iter()
+            .chain(&[Curve::Line(Line::new(b, v)), Curve::Line(Line::new(v, a))])
+            .map(|c| get_curve_winding_number(c, p))
+            .sum();
+        winding != 0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fill::{is_point_in_fill, FillRule};
+    use crate::parser::parse;
+    use crate::path::get_closest_point;
+    use crate::serializer::serialize;
+
+    fn hit(d: &str, x: f64, y: f64, style: StrokeStyle) -> bool {
+        is_point_in_stroke(&parse(d), Vector2(x, y), &style)
+    }
+
+    fn style(width: f64, join: LineJoin, cap: LineCap) -> StrokeStyle {
+        StrokeStyle {
+            width,
+            join,
+            cap,
+            ..StrokeStyle::default()
+        }
+    }
+
+    #[test]
+    fn parse_cases() {
+        assert_eq!(LineJoin::parse("bevel"), Some(LineJoin::Bevel));
+        assert_eq!(LineJoin::parse("miter-clip"), Some(LineJoin::MiterClip));
+        assert_eq!(LineJoin::parse("arcs"), Some(LineJoin::Arcs));
+        assert_eq!(LineJoin::parse("arcs!"), None);
+        assert_eq!(LineCap::parse("square"), Some(LineCap::Square));
+        assert_eq!(LineCap::parse(""), None);
+    }
+
+    #[test]
+    fn is_point_in_stroke_body() {
+        let s = style(2.0, LineJoin::Miter, LineCap::Butt);
+        assert!(hit("M0 0 L10 0", 5.0, 0.9, s));
+        assert!(!hit("M0 0 L10 0", 5.0, 1.1, s));
+        assert!(!hit("M0 0 L10 0", -0.5, 0.0, s));
+        assert!(!hit(
+            "M0 0 L10 0",
+            5.0,
+            0.0,
+            style(0.0, LineJoin::Miter, LineCap::Butt)
+        ));
+
+        let circle = "M0 -10 A10 10 0 0 1 0 10 A10 10 0 0 1 0 -10";
+        assert!(hit(circle, 10.9, 0.0, s));
+        assert!(hit(circle, 0.0, -9.1, s));
+        assert!(!hit(circle, 0.0, 0.0, s));
+        assert!(!hit(circle, 6.0, 6.0, s));
+        assert!(hit(circle, 7.0, 7.0, s));
+
+        let c = "M0 0 C0 -10 10 -10 10 0";
+        assert!(hit(c, 5.0, -7.5, s));
+        assert!(hit(c, 5.0, -6.6, s));
+        assert!(!hit(c, 5.0, -6.4, s));
+    }
+
+    #[test]
+    fn is_point_in_stroke_caps() {
+        let d = "M0 0 L10 0";
+        assert!(!hit(
+            d,
+            -0.5,
+            0.5,
+            style(2.0, LineJoin::Miter, LineCap::Butt)
+        ));
+        assert!(hit(
+            d,
+            -0.5,
+            0.5,
+            style(2.0, LineJoin::Miter, LineCap::Round)
+        ));
+        assert!(!hit(
+            d,
+            -0.9,
+            0.9,
+            style(2.0, LineJoin::Miter, LineCap::Round)
+        ));
+        assert!(hit(
+            d,
+            -0.9,
+            0.9,
+            style(2.0, LineJoin::Miter, LineCap::Square)
+        ));
+        assert!(hit(
+            d,
+            10.9,
+            -0.9,
+            style(2.0, LineJoin::Miter, LineCap::Square)
+        ));
+        assert!(!hit(
+            d,
+            11.1,
+            0.0,
+            style(2.0, LineJoin::Miter, LineCap::Square)
+        ));
+
+        // Caps of a closed subpath don't exist
+        assert!(!hit(
+            "M0 0 L10 0 L10 10 Z",
+            -0.5,
+            -0.5,
+            style(2.0, LineJoin::Bevel, LineCap::Square)
+        ));
+
+        // Zero length subpaths
+        assert!(!hit(
+            "M5 5 z",
+            5.0,
+            5.0,
+            style(2.0, LineJoin::Miter, LineCap::Butt)
+        ));
+        assert!(hit(
+            "M5 5 z",
+            5.5,
+            5.5,
+            style(2.0, LineJoin::Miter, LineCap::Round)
+        ));
+        assert!(hit(
+            "M5 5 l0 0",
+            5.9,
+            5.9,
+            style(2.0, LineJoin::Miter, LineCap::Square)
+        ));
+        assert!(!hit(
+            "M5 5",
+            5.0,
+            5.0,
+            style(2.0, LineJoin::Miter, LineCap::Square)
+        ));
+    }
+
+    #[test]
+    fn get_stroke_outline_cases() {
+        let outline =
+            |d: &str, style: StrokeStyle| serialize(&get_stroke_outline(&parse(d), &style, 0.01));
+        let s = style(2.0, LineJoin::Miter, LineCap::Butt);
+        assert_eq!(outline("M0 0 L10 0", s), "M0 -1 L10 -1 L10 1 L0 1 Z");
+        assert_eq!(
+            outline("M0 0 L10 0 L10 10", s),
+            "M0 -1 L11 -1 L11 10 L9 10 L9 1 L0 1 Z"
+        );
+        assert_eq!(
+            outline("M0 0 h10 v10 h-10 z", s),
+            "M-1 -1 L11 -1 L11 11 L-1 11 Z M1 1 L1 9 L9 9 L9 1 Z"
+        );
+        // Both sides of a line back and forth overlap
+        assert_eq!(outline("M0 0 L10 0 L0 0", s), "M0 -1 L10 -1 L10 1 L0 1 Z");
+        assert_eq!(outline("M5 5", s), "");
+        assert_eq!(
+            outline("M0 0 L10 0", style(0.0, LineJoin::Miter, LineCap::Butt)),
+            ""
+        );
+    }
+
+    #[test]
+    fn get_stroke_outline_matches_hit_test() {
+        let cases = [
+            ("M0 0 L10 0 L10 10", LineJoin::Miter, LineCap::Square),
+            ("M0 0 L10 0 L0 3", LineJoin::MiterClip, LineCap::Butt),
+            ("M0 0 L10 0 L0 3", LineJoin::Bevel, LineCap::Round),
+            ("M0 0 Q10 -10 20 0 T40 0", LineJoin::Round, LineCap::Round),
+            (
+                "M0 0 C0 -10 10 -10 10 0 A5 5 0 0 0 0 0 Z",
+                LineJoin::Miter,
+                LineCap::Butt,
+            ),
+            (
+                "M0 0 A10 5 30 0 1 15 5 L20 -5",
+                LineJoin::Bevel,
+                LineCap::Square,
+            ),
+            (
+                "M0 0 C0 -10 10 -10 10 0 A5 5 0 0 0 0 0 Z",
+                LineJoin::Arcs,
+                LineCap::Butt,
+            ),
+            (
+                "M0 0 A10 5 30 0 1 15 5 L20 -5",
+                LineJoin::Arcs,
+                LineCap::Square,
+            ),
+            ("M5 5 z", LineJoin::Miter, LineCap::Round),
+        ];
+        for (d, join, cap) in cases {
+            let style = StrokeStyle {
+                miter_limit: 2.0,
+                ..style(3.0, join, cap)
+            };
+            let segments = parse(d);
+            let outline = get_stroke_outline(&segments, &style, 0.001);
+            for i in 0..60 {
+                for j in 0..40 {
+                    let p = Vector2(-6.0 + i as f64 * 0.9, -14.0 + j as f64 * 0.75);
+                    let near = get_closest_point(&outline, p).map_or(f64::INFINITY, |c| c.distance);
+                    if near < 0.01 {
+                        continue;
+                    }
+                    assert_eq!(
+                        is_point_in_fill(&outline, p, FillRule::NonZero),
+                        is_point_in_stroke(&segments, p, &style),
+                        "{} at {:?}",
+                        d,
+                        p
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn is_point_in_stroke_joins() {
+        let d = "M0 0 L10 0 L10 10";
+        let corner = (10.9, -0.9);
+        let miter = style(2.0, LineJoin::Miter, LineCap::Butt);
+        assert!(hit(d, corner.0, corner.1, miter));
+        assert!(!hit(
+            d,
+            corner.0,
+            corner.1,
+            style(2.0, LineJoin::Bevel, LineCap::Butt)
+        ));
+        assert!(hit(
+            d,
+            10.4,
+            -0.4,
+            style(2.0, LineJoin::Bevel, LineCap::Butt)
+        ));
+        assert!(!hit(
+            d,
+            corner.0,
+            corner.1,
+            style(2.0, LineJoin::Round, LineCap::Butt)
+        ));
+        assert!(hit(
+            d,
+            10.6,
+            -0.6,
+            style(2.0, LineJoin::Round, LineCap::Butt)
+        ));
+        assert!(!hit(
+            d,
+            corner.0,
+            corner.1,
+            StrokeStyle {
+                miter_limit: 1.2,
+                ..miter
+            }
+        ));
+        let clip = StrokeStyle {
+            miter_limit: 1.2,
+            ..style(2.0, LineJoin::MiterClip, LineCap::Butt)
+        };
+        assert!(hit(d, 10.8, -0.8, clip));
+        assert!(!hit(d, corner.0, corner.1, clip));
+
+        // The join at the start of a closed subpath
+        assert!(hit("M0 0 L10 0 L10 10 Z", -0.4, -0.9, miter));
+    }
+}
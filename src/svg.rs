@@ -0,0 +1,529 @@
+use std::convert::TryFrom;
+
+use crate::parser::{self, parse_segments, scan_number, Lexer};
+use crate::path::{get_path_length, get_subpaths, PathSegment};
+use crate::segment::Segment;
+use crate::shapes;
+use crate::vector::{Arc, Bezier2, Bezier3, Curve, Line, Vector2};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgPath {
+    pub id: Option<String>,
+    // Local name of the element such as "path" or "rect"
+    pub tag: String,
+    // Absolute segments in the user space of the document
+    pub segments: Vec<PathSegment>,
+    // Measured in the user space of the element, as `getTotalLength` does
+    pub length: f64,
+}
+
+// Every path and basic shape in the document with the transforms of its
+// ancestors applied, in document order. Reading stops at malformed markup,
+// and invalid path data draws nothing.
+pub fn get_svg_paths(svg: &str) -> Vec<SvgPath> {
+    let mut ret: Vec<SvgPath> = vec![];
+    let mut stack: Vec<Transform> = vec![];
+    for token in tokenize(svg) {
+        match token {
+            Token::Start { name, attrs, empty } => {
+                let parent = stack.last().copied().unwrap_or(Transform::IDENTITY);
+                let transform = match get_attr(&attrs, "transform") {
+                    Some(v) => parent.then(&parse_transform(v)),
+                    None => parent,
+                };
+                if let Some(segments) = get_shape(name, &attrs) {
+                    ret.push(SvgPath {
+                        id: get_attr(&attrs, "id").map(|s| s.to_string()),
+                        tag: name.to_string(),
+                        segments: resolve(&segments, &transform),
+                        length: get_path_length(&segments),
+                    });
+                }
+                if !empty {
+                    stack.push(transform);
+                }
+            }
+            Token::End => {
+                stack.pop();
+            }
+        }
+    }
+    ret
+}
+
+fn get_shape(name: &str, attrs: &[(&str, String)]) -> Option<Vec<PathSegment>> {
+    let length = |key: &str| get_attr(attrs, key).and_then(parse_length);
+    let number = |key: &str| length(key).unwrap_or(0.0);
+    let ret = match name {
+        // Segments before an error are still drawn
+        "path" => parse_segments(get_attr(attrs, "d").unwrap_or("").as_bytes())
+            .map_while(Result::ok)
+            .map(|s| PathSegment::from(&s))
+            .collect(),
+        "rect" => shapes::rect(
+            number("x"),
+            number("y"),
+            number("width"),
+            number("height"),
+            length("rx"),
+            length("ry"),
+        ),
+        "circle" => shapes::circle(number("cx"), number("cy"), number("r")),
+        "ellipse" => shapes::ellipse(number("cx"), number("cy"), length("rx"), length("ry")),
+        "line" => shapes::line(number("x1"), number("y1"), number("x2"), number("y2")),
+        "polyline" | "polygon" => {
+            let values = parse_numbers(get_attr(attrs, "points").unwrap_or(""));
+            let points: Vec<Vector2> = values
+                .chunks_exact(2)
+                .map(|c| Vector2(c[0], c[1]))
+                .collect();
+            if name == "polyline" {
+                shapes::polyline(&points)
+            } else {
+                shapes::polygon(&points)
+            }
+        }
+        _ => return None,
+    };
+    Some(ret)
+}
+
+// Transformed absolute segments
+fn resolve(segments: &[PathSegment], transform: &Transform) -> Vec<PathSegment> {
+    let mut ret: Vec<PathSegment> = vec![];
+    for subpath in get_subpaths(segments) {
+        let start = transform.apply(subpath.start);
+        ret.push(PathSegment::new('M', vec![start.0, start.1]));
+        for (index, curve) in &subpath.curves {
+            ret.push(match Segment::try_from(&segments[*index]) {
+                Ok(Segment::Close { .. }) => PathSegment::new('Z', vec![]),
+                _ => PathSegment::from_curve(&transform.apply_curve(curve)),
+            });
+        }
+    }
+    ret
+}
+
+// Maps `(x, y)` to `(a x + c y + e, b x + d y + f)` as SVG's matrix
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Transform([f64; 6]);
+
+impl Transform {
+    const IDENTITY: Transform = Transform([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    // Applies `other` first, then this
+    fn then(&self, other: &Transform) -> Transform {
+        let [a, b, c, d, e, f] = self.0;
+        let [g, h, i, j, k, l] = other.0;
+        Transform([
+            a * g + c * h,
+            b * g + d * h,
+            a * i + c * j,
+            b * i + d * j,
+            a * k + c * l + e,
+            b * k + d * l + f,
+        ])
+    }
+
+    fn apply(&self, p: Vector2) -> Vector2 {
+        let [a, b, c, d, e, f] = self.0;
+        Vector2(a * p.0 + c * p.1 + e, b * p.0 + d * p.1 + f)
+    }
+
+    fn apply_curve(&self, curve: &Curve) -> Curve {
+        match curve {
+            Curve::Line(c) => Curve::Line(Line::new(self.apply(c.p0), self.apply(c.p1))),
+            Curve::Bezier2(c) => Curve::Bezier2(Bezier2::new(
+                self.apply(c.p0),
+                self.apply(c.p1),
+                self.apply(c.p2),
+            )),
+            Curve::Bezier3(c) => Curve::Bezier3(Bezier3::new(
+                self.apply(c.p0),
+                self.apply(c.p1),
+                self.apply(c.p2),
+                self.apply(c.p3),
+            )),
+            Curve::Arc(c) => self.apply_arc(c),
+        }
+    }
+
+    // The image of an ellipse is another ellipse whose radii are the
+    // singular values of the matrix mapping the unit circle onto it
+    fn apply_arc(&self, arc: &Arc) -> Curve {
+        let [a, b, c, d, _, _] = self.0;
+        let (sin, cos) = arc.rotation.sin_cos();
+        let u = Vector2(a * cos + c * sin, b * cos + d * sin).multi(arc.rx);
+        let v = Vector2(-a * sin + c * cos, -b * sin + d * cos).multi(arc.ry);
+        // Symmetric u u^T + v v^T
+        let p = u.0 * u.0 + v.0 * v.0;
+        let q = u.0 * u.1 + v.0 * v.1;
+        let r = u.1 * u.1 + v.1 * v.1;
+        let mid = (p + r) / 2.0;
+        let diff = (((p - r) / 2.0).powi(2) + q * q).sqrt();
+        let rx = (mid + diff).sqrt();
+        let ry = (mid - diff).max(0.0).sqrt();
+
+        let p0 = self.apply(arc.p0);
+        let p1 = self.apply(arc.p1);
+        if ry <= rx * 1e-12 {
+            return Curve::Line(Line::new(p0, p1));
+        }
+        let rotation = 0.5 * (2.0 * q).atan2(p - r);
+        let flip = a * d - b * c < 0.0;
+        Curve::Arc(Arc::new(
+            p0,
+            rx,
+            ry,
+            rotation.to_degrees(),
+            arc.dtheta.abs() > std::f64::consts::PI,
+            (arc.dtheta > 0.0) != flip,
+            p1,
+        ))
+    }
+}
+
+// Invalid lists are ignored as SVG does
+fn parse_transform(s: &str) -> Transform {
+    let mut ret = Transform::IDENTITY;
+    let mut rest = s;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            return ret;
+        }
+        let (name, args) = match (rest.find('('), rest.find(')')) {
+            (Some(open), Some(close)) if open < close => {
+                let name = rest[..open].trim();
+                let args = parse_numbers(&rest[(open + 1)..close]);
+                rest = &rest[(close + 1)..];
+                (name, args)
+            }
+            _ => return Transform::IDENTITY,
+        };
+        let t = match (name, args.as_slice()) {
+            ("matrix", [a, b, c, d, e, f]) => Transform([*a, *b, *c, *d, *e, *f]),
+            ("translate", [x]) => Transform([1.0, 0.0, 0.0, 1.0, *x, 0.0]),
+            ("translate", [x, y]) => Transform([1.0, 0.0, 0.0, 1.0, *x, *y]),
+            ("scale", [x]) => Transform([*x, 0.0, 0.0, *x, 0.0, 0.0]),
+            ("scale", [x, y]) => Transform([*x, 0.0, 0.0, *y, 0.0, 0.0]),
+            ("rotate", [r]) => get_rotation(*r),
+            ("rotate", [r, x, y]) => Transform([1.0, 0.0, 0.0, 1.0, *x, *y])
+                .then(&get_rotation(*r))
+                .then(&Transform([1.0, 0.0, 0.0, 1.0, -x, -y])),
+            ("skewX", [r]) => Transform([1.0, 0.0, r.to_radians().tan(), 1.0, 0.0, 0.0]),
+            ("skewY", [r]) => Transform([1.0, r.to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+            _ => return Transform::IDENTITY,
+        };
+        ret = ret.then(&t);
+    }
+}
+
+fn get_rotation(degrees: f64) -> Transform {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    Transform([cos, sin, -sin, cos, 0.0, 0.0])
+}
+
+// Numbers separated by whitespace, commas or signs, split as in path data.
+// Reading stops at the first invalid one.
+fn parse_numbers(s: &str) -> Vec<f64> {
+    Lexer::new(s.as_bytes())
+        .map_while(|token| match token {
+            Ok(parser::Token::Number(v)) => v.parse().ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+// Absolute lengths in px. Relative units such as "%" and "em" are unknown
+// here.
+fn parse_length(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let end = scan_number(s.as_bytes(), 0)?;
+    let v: f64 = s[..end].parse().ok()?;
+    let scale = match s[end..].trim() {
+        "" | "px" => 1.0,
+        "in" => 96.0,
+        "cm" => 96.0 / 2.54,
+        "mm" => 96.0 / 25.4,
+        "pt" => 4.0 / 3.0,
+        "pc" => 16.0,
+        _ => return None,
+    };
+    Some(v * scale)
+}
+
+fn get_attr<'a>(attrs: &'a [(&str, String)], key: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Start {
+        // Without the namespace prefix
+        name: &'a str,
+        attrs: Vec<(&'a str, String)>,
+        empty: bool,
+    },
+    End,
+}
+
+// Tags of the document. Text, comments, CDATA, processing instructions and
+// declarations are skipped.
+fn tokenize(svg: &str) -> Vec<Token<'_>> {
+    let bytes = svg.as_bytes();
+    let mut ret: Vec<Token> = vec![];
+    let mut i = 0;
+    while let Some(offset) = svg[i..].find('<') {
+        i += offset;
+        let rest = &svg[i..];
+        let skip_to = |end: &str| rest.find(end).map(|j| i + j + end.len());
+        let next = if rest.starts_with("<!--") {
+            skip_to("-->")
+        } else if rest.starts_with("<![CDATA[") {
+            skip_to("]]>")
+        } else if rest.starts_with("<?") {
+            skip_to("?>")
+        } else if rest.starts_with("<!") {
+            // The internal subset of DOCTYPE may have '>' in it
+            match (rest.find('['), rest.find('>')) {
+                (Some(open), Some(close)) if open < close => skip_subset(rest, open).map(|j| i + j),
+                _ => skip_to(">"),
+            }
+        } else if rest.starts_with("</") {
+            ret.push(Token::End);
+            skip_to(">")
+        } else {
+            match read_start_tag(svg, i + 1) {
+                Some((token, end)) => {
+                    ret.push(token);
+                    Some(end)
+                }
+                None => None,
+            }
+        };
+        match next {
+            Some(j) if j <= bytes.len() => i = j,
+            _ => break,
+        }
+    }
+    ret
+}
+
+// End of a DOCTYPE whose internal subset starts at `open`, which is a ']'
+// followed by '>' with only whitespace between them
+fn skip_subset(rest: &str, open: usize) -> Option<usize> {
+    let mut i = open;
+    loop {
+        i += rest[i..].find(']')? + 1;
+        let after = rest[i..].trim_start();
+        if after.starts_with('>') {
+            return Some(rest.len() - after.len() + 1);
+        }
+    }
+}
+
+// The start tag after '<' and the index after its '>'
+fn read_start_tag(svg: &str, from: usize) -> Option<(Token<'_>, usize)> {
+    let bytes = svg.as_bytes();
+    let is_name_end = |b: u8| b.is_ascii_whitespace() || b == b'/' || b == b'>' || b == b'=';
+    let read_name = |mut i: usize| {
+        let begin = i;
+        while i < bytes.len() && !is_name_end(bytes[i]) {
+            i += 1;
+        }
+        (&svg[begin..i], i)
+    };
+    let skip_whitespace = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    };
+
+    let (qname, mut i) = read_name(from);
+    if qname.is_empty() {
+        return None;
+    }
+    let name = qname.rsplit(':').next().unwrap_or(qname);
+    let mut attrs: Vec<(&str, String)> = vec![];
+    loop {
+        i = skip_whitespace(i);
+        match bytes.get(i) {
+            Some(b'>') => {
+                return Some((
+                    Token::Start {
+                        name,
+                        attrs,
+                        empty: false,
+                    },
+                    i + 1,
+                ))
+            }
+            Some(b'/') if bytes.get(i + 1) == Some(&b'>') => {
+                return Some((
+                    Token::Start {
+                        name,
+                        attrs,
+                        empty: true,
+                    },
+                    i + 2,
+                ))
+            }
+            Some(_) => {}
+            None => return None,
+        }
+
+        let (key, j) = read_name(i);
+        i = skip_whitespace(j);
+        if key.is_empty() || bytes.get(i) != Some(&b'=') {
+            return None;
+        }
+        i = skip_whitespace(i + 1);
+        let quote = *bytes.get(i).filter(|b| **b == b'"' || **b == b'\'')?;
+        let close = svg[(i + 1)..].find(quote as char)? + i + 1;
+        attrs.push((key, decode_entities(&svg[(i + 1)..close])));
+        i = close + 1;
+    }
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut ret = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        ret.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                e if e.starts_with("#x") => u32::from_str_radix(&e[2..], 16)
+                    .ok()
+                    .and_then(char::from_u32),
+                e if e.starts_with('#') => e[1..].parse().ok().and_then(char::from_u32),
+                _ => None,
+            }?;
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                ret.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                ret.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    ret.push_str(rest);
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serializer::serialize;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn tokenize_doctype_subset() {
+        let svg = "<!DOCTYPE svg [ <!ENTITY e \"]\"> ] ><svg/>";
+        assert_eq!(tokenize(svg).len(), 1);
+        assert_eq!(tokenize("<!DOCTYPE svg [\n]\n>").len(), 0);
+    }
+
+    #[test]
+    fn get_svg_paths_cases() {
+        let svg = r#"<?xml version="1.0"?>
+<!DOCTYPE svg [ <!ENTITY e "x"> ]>
+<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24">
+  <!-- <path d="M0 0 h100"/> -->
+  <title>a &lt; b</title>
+  <path id="a" d="M0 0
+    h10 v10"/>
+  <g transform="translate(10, 20) scale(2)">
+    <g transform="rotate(90)">
+      <line id="b&amp;c" x1="0" y1="0" x2="5" y2="0" />
+    </g>
+    <rect width="1in" height="2" rx="auto"></rect>
+  </g>
+  <svg:path d="M0 0 h5 L1e2 0 L 1 K"/>
+  <path d="K"/>
+</svg>"#;
+        let ret = get_svg_paths(svg);
+        assert_eq!(ret.len(), 5);
+
+        assert_eq!(ret[0].id, Some("a".to_string()));
+        assert_eq!(ret[0].tag, "path");
+        assert_eq!(serialize(&ret[0].segments), "M0 0 L10 0 L10 10");
+        assert_eq!(ret[0].length, 20.0);
+
+        assert_eq!(ret[1].id, Some("b&c".to_string()));
+        let p = &ret[1].segments[1].values;
+        assert!((p[0] - 10.0).abs() < 1e-9 && (p[1] - 30.0).abs() < 1e-9);
+        // Lengths stay in the user space of the element
+        assert!((ret[1].length - 5.0).abs() < 1e-9);
+
+        assert_eq!(ret[2].tag, "rect");
+        assert_eq!(ret[2].id, None);
+        assert!((ret[2].length - 2.0 * (96.0 + 2.0)).abs() < 1e-9);
+
+        // Invalid data draws what comes before it
+        assert_eq!(ret[3].tag, "path");
+        assert_eq!(serialize(&ret[3].segments), "M0 0 L5 0 L100 0");
+        assert_eq!(ret[3].length, 100.0);
+        assert_eq!(ret[4].segments, vec![]);
+        assert_eq!(ret[4].length, 0.0);
+    }
+
+    #[test]
+    fn get_svg_paths_transformed_arcs() {
+        let svg = r#"<svg><circle transform="matrix(2 0 0 1 0 0) skewX(0)" r="10"/>
+            <g transform="scale(-1 1) rotate(30)"><circle r="10" cx="5"/></g></svg>"#;
+        let ret = get_svg_paths(svg);
+        assert_eq!(
+            serialize(&ret[0].segments),
+            "M20 0 A20 10 0 0 1 0 10 A20 10 0 0 1 -20 0 A20 10 0 0 1 0 -10 A20 10 0 0 1 20 0 Z"
+        );
+        assert!((ret[0].length - 20.0 * PI).abs() < 0.1);
+
+        // Mirrored arcs keep going around the circle
+        assert!((ret[1].length - 20.0 * PI).abs() < 0.1);
+        let last = &ret[1].segments[4].values;
+        assert_eq!(last[3..5], [0.0, 0.0]);
+    }
+
+    #[test]
+    fn parse_transform_cases() {
+        assert_eq!(
+            parse_transform("translate(1 2)scale(3,4)"),
+            Transform([3.0, 0.0, 0.0, 4.0, 1.0, 2.0])
+        );
+        let t = parse_transform("rotate(90 10 0)");
+        let p = t.apply(Vector2(20.0, 0.0));
+        assert!((p - Vector2(10.0, 10.0)).norm() < 1e-9);
+        assert_eq!(parse_transform("scale(1 2 3)"), Transform::IDENTITY);
+        assert_eq!(parse_transform("translate(1"), Transform::IDENTITY);
+    }
+
+    #[test]
+    fn parse_number_cases() {
+        assert_eq!(
+            parse_numbers(" 1,-2.5e1 +.5-1e x"),
+            vec![1.0, -25.0, 0.5, -1.0]
+        );
+        // Dots are read as the path parser does
+        assert_eq!(parse_numbers("1 .5.5 2"), vec![1.0]);
+        assert_eq!(parse_length("1.5mm"), Some(1.5 * 96.0 / 25.4));
+        assert_eq!(parse_length(" 3 "), Some(3.0));
+        assert_eq!(parse_length("50%"), None);
+        assert_eq!(parse_length("auto"), None);
+    }
+}
@@ -0,0 +1,23 @@
+use std::fmt::Debug;
+
+use crate::vector::Vector2;
+
+pub(crate) trait Distance: Copy + Debug {
+    fn distance(self, other: Self) -> f64;
+}
+
+impl Distance for f64 {
+    fn distance(self, other: Self) -> f64 {
+        (self - other).abs()
+    }
+}
+
+impl Distance for Vector2 {
+    fn distance(self, other: Self) -> f64 {
+        (self - other).norm()
+    }
+}
+
+pub(crate) fn assert_close<T: Distance>(a: T, b: T) {
+    assert!(a.distance(b) < 1e-9, "{:?} != {:?}", a, b);
+}
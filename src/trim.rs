@@ -0,0 +1,338 @@
+use crate::path::{get_subpaths, PathSegment, Subpath, SPLIT_COUNT};
+use crate::vector::{Curve, Lerpable, Vector2};
+
+// Returns the part of the path between two distances from its start.
+// Distances are clamped to the path length, and `from > to` wraps around the
+// end of the path like "Trim Paths" does. The wrapped parts are joined when
+// the path is a single closed subpath.
+pub fn get_subpath(segments: &[PathSegment], from: f64, to: f64) -> Vec<PathSegment> {
+    let subpaths = get_subpaths(segments);
+    let lengths: Vec<Vec<f64>> = subpaths
+        .iter()
+        .map(|s| {
+            s.curves
+                .iter()
+                .map(|(_, c)| c.get_appro_length(SPLIT_COUNT))
+                .collect()
+        })
+        .collect();
+    let total: f64 = lengths.iter().flatten().sum();
+
+    let from = from.max(0.0).min(total);
+    let to = to.max(0.0).min(total);
+    let mut ret: Vec<PathSegment> = vec![];
+    let append = |ret: &mut Vec<PathSegment>, from: f64, to: f64, joined: bool| {
+        let mut s0 = 0.0;
+        for (subpath, curve_lengths) in subpaths.iter().zip(&lengths) {
+            append_range(ret, subpath, curve_lengths, from - s0, to - s0, joined);
+            s0 += curve_lengths.iter().sum::<f64>();
+        }
+    };
+
+    if from <= to {
+        append(&mut ret, from, to, false);
+    } else {
+        let joinable = subpaths.len() == 1 && subpaths[0].closed;
+        append(&mut ret, from, total, false);
+        append(&mut ret, 0.0, to, joinable);
+    }
+
+    ret
+}
+
+// Upper bound of the dashes from `get_dashes`
+pub const MAX_DASH_COUNT: usize = 1 << 20;
+
+// https://www.w3.org/TR/SVG2/painting.html#StrokeDashing
+// Each dash becomes its own subpath, and the pattern restarts at every
+// subpath. Negative values or a zero sum in `dasharray` disable dashing as
+// in SVG, so the path comes back as it is. So does a pattern so small
+// relative to the length that it'd take more than `MAX_DASH_COUNT` values,
+// as Skia does. Zero length dashes become a line to the same point, which
+// still gets caps.
+pub fn get_dashes(segments: &[PathSegment], dasharray: &[f64], offset: f64) -> Vec<PathSegment> {
+    let sum: f64 = dasharray.iter().sum();
+    if dasharray.iter().any(|v| *v < 0.0 || !v.is_finite()) || sum <= 0.0 || !offset.is_finite() {
+        return segments.to_vec();
+    }
+    // An odd number of values is repeated to yield an even number
+    let pattern: Vec<f64> = if dasharray.len() % 2 == 1 {
+        dasharray.iter().chain(dasharray).copied().collect()
+    } else {
+        dasharray.to_vec()
+    };
+    let period = pattern.iter().sum::<f64>();
+
+    let subpaths = get_subpaths(segments);
+    let lengths: Vec<Vec<f64>> = subpaths
+        .iter()
+        .map(|s| {
+            s.curves
+                .iter()
+                .map(|(_, c)| c.get_appro_length(SPLIT_COUNT))
+                .collect()
+        })
+        .collect();
+    let count: f64 = lengths
+        .iter()
+        .map(|l| ((l.iter().sum::<f64>() + period) / period).ceil() * pattern.len() as f64)
+        .sum();
+    if count.is_nan() || count > MAX_DASH_COUNT as f64 {
+        return segments.to_vec();
+    }
+
+    let mut ret: Vec<PathSegment> = vec![];
+    for (subpath, curve_lengths) in subpaths.iter().zip(&lengths) {
+        let length: f64 = curve_lengths.iter().sum();
+        // The dash containing the start begins at `-offset`
+        let mut position = -offset.rem_euclid(period);
+        let mut i = 0;
+        while position < length {
+            let next = position + pattern[i % pattern.len()];
+            if i % 2 == 0 {
+                let lo = position.max(0.0);
+                let hi = next.min(length);
+                if lo < hi {
+                    append_range(&mut ret, subpath, curve_lengths, lo, hi, false);
+                } else if position == next && position >= 0.0 {
+                    let p = get_point(subpath, curve_lengths, position);
+                    ret.push(PathSegment::new('M', vec![p.0, p.1]));
+                    ret.push(PathSegment::new('L', vec![p.0, p.1]));
+                }
+            }
+            position = next;
+            i += 1;
+        }
+    }
+    ret
+}
+
+fn get_point(subpath: &Subpath, curve_lengths: &[f64], distance: f64) -> Vector2 {
+    let mut rest = distance;
+    for ((_, curve), d) in subpath.curves.iter().zip(curve_lengths) {
+        if rest <= *d {
+            return curve.lerp(curve.get_appro_t_at_length(rest, SPLIT_COUNT));
+        }
+        rest -= d;
+    }
+    subpath.start
+}
+
+// Appends the part of the subpath between two distances from its start
+fn append_range(
+    ret: &mut Vec<PathSegment>,
+    subpath: &Subpath,
+    curve_lengths: &[f64],
+    from: f64,
+    to: f64,
+    joined: bool,
+) {
+    let length: f64 = curve_lengths.iter().sum();
+    let lo = from.max(0.0);
+    let hi = to.min(length);
+    if lo >= hi {
+        return;
+    }
+
+    let whole = lo == 0.0 && hi == length && subpath.closed;
+    let mut pieces: Vec<Curve> = vec![];
+    let mut c0 = 0.0;
+    for ((_, curve), d) in subpath.curves.iter().zip(curve_lengths) {
+        let c1 = c0 + d;
+        let l = lo.max(c0);
+        let h = hi.min(c1);
+        if l < h {
+            pieces.push(curve.subcurve(
+                curve.get_appro_t_at_length(l - c0, SPLIT_COUNT),
+                curve.get_appro_t_at_length(h - c0, SPLIT_COUNT),
+            ));
+        }
+        c0 = c1;
+    }
+
+    if whole {
+        // The closing line is replaced by 'Z'
+        pieces.pop();
+    }
+    if pieces.is_empty() && !whole {
+        return;
+    }
+
+    if !joined || ret.is_empty() {
+        let p = if whole {
+            subpath.start
+        } else {
+            pieces[0].start()
+        };
+        ret.push(PathSegment::new('M', vec![p.0, p.1]));
+    }
+    ret.extend(pieces.iter().map(PathSegment::from_curve));
+    if whole {
+        ret.push(PathSegment::new('Z', vec![]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::serializer::serialize;
+
+    fn trim(d: &str, from: f64, to: f64) -> Vec<PathSegment> {
+        get_subpath(&parse(d), from, to)
+    }
+
+    #[test]
+    fn get_subpath_open_lines() {
+        assert_eq!(
+            trim("M0 0 L10 0 L10 10", 5.0, 15.0),
+            vec![
+                PathSegment::new('M', vec![5.0, 0.0]),
+                PathSegment::new('L', vec![10.0, 0.0]),
+                PathSegment::new('L', vec![10.0, 5.0]),
+            ]
+        );
+        assert_eq!(
+            trim("M0 0 L10 0 L10 10", -5.0, 100.0),
+            vec![
+                PathSegment::new('M', vec![0.0, 0.0]),
+                PathSegment::new('L', vec![10.0, 0.0]),
+                PathSegment::new('L', vec![10.0, 10.0]),
+            ]
+        );
+        assert_eq!(trim("M0 0 L10 0 L10 10", 5.0, 5.0), vec![]);
+        assert_eq!(trim("", 0.0, 10.0), vec![]);
+    }
+
+    #[test]
+    fn get_subpath_across_subpaths() {
+        assert_eq!(
+            trim("M0 0 h10 M0 10 h10", 5.0, 15.0),
+            vec![
+                PathSegment::new('M', vec![5.0, 0.0]),
+                PathSegment::new('L', vec![10.0, 0.0]),
+                PathSegment::new('M', vec![0.0, 10.0]),
+                PathSegment::new('L', vec![5.0, 10.0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_subpath_closed() {
+        assert_eq!(
+            trim("M0 0 h10 v10 h-10 z", 0.0, 40.0),
+            vec![
+                PathSegment::new('M', vec![0.0, 0.0]),
+                PathSegment::new('L', vec![10.0, 0.0]),
+                PathSegment::new('L', vec![10.0, 10.0]),
+                PathSegment::new('L', vec![0.0, 10.0]),
+                PathSegment::new('Z', vec![]),
+            ]
+        );
+        assert_eq!(
+            trim("M0 0 h10 v10 h-10 z", 25.0, 40.0),
+            vec![
+                PathSegment::new('M', vec![5.0, 10.0]),
+                PathSegment::new('L', vec![0.0, 10.0]),
+                PathSegment::new('L', vec![0.0, 0.0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_subpath_wrap_around() {
+        assert_eq!(
+            trim("M0 0 h10 v10 h-10 z", 35.0, 5.0),
+            vec![
+                PathSegment::new('M', vec![0.0, 5.0]),
+                PathSegment::new('L', vec![0.0, 0.0]),
+                PathSegment::new('L', vec![5.0, 0.0]),
+            ]
+        );
+        assert_eq!(
+            trim("M0 0 h10 v10", 15.0, 5.0),
+            vec![
+                PathSegment::new('M', vec![10.0, 5.0]),
+                PathSegment::new('L', vec![10.0, 10.0]),
+                PathSegment::new('M', vec![0.0, 0.0]),
+                PathSegment::new('L', vec![5.0, 0.0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_dashes_cases() {
+        let dash =
+            |d: &str, array: &[f64], offset: f64| serialize(&get_dashes(&parse(d), array, offset));
+        assert_eq!(dash("M0 0 h10", &[3.0, 2.0], 0.0), "M0 0 L3 0 M5 0 L8 0");
+        // Odd arrays repeat, so the second dash is 2 long
+        assert_eq!(
+            dash("M0 0 h10", &[3.0, 2.0, 1.0], 0.0),
+            "M0 0 L3 0 M5 0 L6 0 M9 0 L10 0"
+        );
+        assert_eq!(
+            dash("M0 0 h10", &[3.0, 2.0], 1.0),
+            "M0 0 L2 0 M4 0 L7 0 M9 0 L10 0"
+        );
+        assert_eq!(dash("M0 0 h10", &[3.0, 2.0], -1.0), "M1 0 L4 0 M6 0 L9 0");
+
+        // Across corners and restarting at each subpath
+        assert_eq!(
+            dash("M0 0 h4 v4 M10 0 h4", &[6.0, 1.0], 0.0),
+            "M0 0 L4 0 L4 2 M4 3 L4 4 M10 0 L14 0"
+        );
+        assert_eq!(
+            dash("M0 0 h10 v10 h-10 z", &[100.0], 0.0),
+            "M0 0 L10 0 L10 10 L0 10 Z"
+        );
+
+        // Zero length dashes are kept as points
+        assert_eq!(
+            dash("M0 0 h10", &[0.0, 4.0], 0.0),
+            "M0 0 L0 0 M4 0 L4 0 M8 0 L8 0"
+        );
+        assert_eq!(
+            dash("M0 0 h4 v4", &[3.0, 0.0, 0.0, 2.0], 0.0),
+            "M0 0 L3 0 M3 0 L3 0 M4 1 L4 4"
+        );
+
+        // Solid when the array is in error or sums to zero
+        assert_eq!(dash("M0 0 h10", &[3.0, -1.0], 0.0), "M0 0 h10");
+        assert_eq!(dash("M0 0 h10", &[0.0, 0.0], 0.0), "M0 0 h10");
+        assert_eq!(dash("M0 0 h10", &[], 0.0), "M0 0 h10");
+
+        // Solid when it'd take too many dashes
+        assert_eq!(dash("M0 0 h10", &[1e-20, 1e-20], 0.0), "M0 0 h10");
+        assert_eq!(dash("M0 0 h10", &[1e-6, 1e-6], 0.0), "M0 0 h10");
+        let ret = get_dashes(&parse("M0 0 h8"), &[1.0 / 1024.0], 0.0);
+        assert_eq!(ret.iter().filter(|s| s._type == 'M').count(), 4096);
+    }
+
+    #[test]
+    fn get_subpath_curves() {
+        let ret = trim("M0 0 Q10 0 10 10", 0.0, 100.0);
+        assert_eq!(ret[1], PathSegment::new('Q', vec![10.0, 0.0, 10.0, 10.0]));
+
+        let ret = trim("M0 0 C10 0 0 10 10 10", 0.0, 100.0);
+        assert_eq!(
+            ret[1],
+            PathSegment::new('C', vec![10.0, 0.0, 0.0, 10.0, 10.0, 10.0])
+        );
+
+        let ret = trim("M10 0 A10 10 0 0 1 -10 0", 0.0, 100.0);
+        assert_eq!(ret[1]._type, 'A');
+        assert_eq!(ret[1].values[0].round(), 10.0);
+        assert_eq!(ret[1].values[3], 0.0);
+        assert_eq!(ret[1].values[4], 1.0);
+        assert_eq!(ret[1].values[5].round(), -10.0);
+        assert_eq!(ret[1].values[6].round(), 0.0);
+
+        let half = trim(
+            "M10 0 A10 10 0 0 1 -10 0",
+            0.0,
+            10.0 * std::f64::consts::PI / 2.0,
+        );
+        assert_eq!(half[1].values[5].round(), 0.0);
+        assert_eq!(half[1].values[6].round(), 10.0);
+    }
+}
@@ -3,6 +3,8 @@ use std::ops::Add;
 use std::ops::Mul;
 use std::ops::Sub;
 
+use crate::solver::{bisect, solve_polynomial, solve_quadratic};
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Vector2(pub f64, pub f64);
 
@@ -54,11 +56,15 @@ impl Vector2 {
     }
 
     pub fn radian(self, to: Vector2) -> f64 {
-        (self.dot(to) / self.norm() / to.norm()).acos() * self.cross(to).signum()
+        // Rounding can push the cosine out of [-1, 1] for (anti)parallel vectors
+        (self.dot(to) / self.norm() / to.norm())
+            .clamp(-1.0, 1.0)
+            .acos()
+            * self.cross(to).signum()
     }
 }
 
-pub fn get_polyline_length(points: &Vec<Vector2>) -> f64 {
+pub fn get_polyline_length(points: &[Vector2]) -> f64 {
     if points.len() <= 1 {
         return 0.0;
     }
@@ -102,22 +108,223 @@ pub trait Lerpable {
 
         points
     }
+
+    // Length of the same polyline as `get_appro_length` up to `t`
+    fn get_appro_length_at_t(&self, t: f64, split: usize) -> f64 {
+        if t <= 0.0 {
+            return 0.0;
+        }
+
+        let points = self.get_appro_points(split);
+        let n = points.len() - 1;
+        let k = t.min(1.0) * n as f64;
+        let mut length = 0.0;
+        for i in 0..n {
+            let d = (points[i + 1] - points[i]).norm();
+            if k <= (i + 1) as f64 {
+                return length + d * (k - i as f64);
+            }
+            length += d;
+        }
+        length
+    }
+
+    // Inverse of `get_appro_length`: walks the same polyline so that both agree
+    fn get_appro_t_at_length(&self, length: f64, split: usize) -> f64 {
+        if length <= 0.0 {
+            return 0.0;
+        }
+
+        let points = self.get_appro_points(split);
+        let n = points.len() - 1;
+        let mut rest = length;
+        for i in 0..n {
+            let d = (points[i + 1] - points[i]).norm();
+            if rest <= d {
+                let r = if d == 0.0 { 0.0 } else { rest / d };
+                return (i as f64 + r) / n as f64;
+            }
+            rest -= d;
+        }
+        1.0
+    }
 }
 
-fn lerp(p0: &Vector2, p1: &Vector2, t: f64) -> Vector2 {
+fn in_open_unit(mut ts: Vec<f64>) -> Vec<f64> {
+    ts.retain(|t| 0.0 < *t && *t < 1.0);
+    ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    ts
+}
+
+// Parameters where `p - b(t)` is perpendicular to `b'(t)`, given `b` in power basis
+fn get_normal_foot_t_polynomial(coeffs: &[Vector2], p: Vector2) -> Vec<f64> {
+    let mut b: Vec<Vector2> = coeffs.to_vec();
+    b[0] = b[0] - p;
+    let db: Vec<Vector2> = coeffs
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| c.multi(i as f64))
+        .collect();
+
+    let mut product = vec![0.0; b.len() + db.len() - 1];
+    for (i, u) in b.iter().enumerate() {
+        for (j, v) in db.iter().enumerate() {
+            product[i + j] += u.dot(*v);
+        }
+    }
+    solve_polynomial(&product, 0.0, 1.0)
+}
+
+pub(crate) fn lerp(p0: &Vector2, p1: &Vector2, t: f64) -> Vector2 {
     *p0 + (*p1 - *p0).multi(t)
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Line {
+    pub(crate) p0: Vector2,
+    pub(crate) p1: Vector2,
+}
+
+impl Line {
+    pub fn new(p0: Vector2, p1: Vector2) -> Self {
+        Self { p0, p1 }
+    }
+
+    pub fn p0(&self) -> Vector2 {
+        self.p0
+    }
+
+    pub fn p1(&self) -> Vector2 {
+        self.p1
+    }
+
+    pub fn split_at(&self, t: f64) -> (Self, Self) {
+        let p = self.lerp(t);
+        (Self::new(self.p0, p), Self::new(p, self.p1))
+    }
+
+    pub fn derivative(&self, _t: f64) -> Vector2 {
+        self.p1 - self.p0
+    }
+
+    // Parameters in [0, 1] where `p - lerp(t)` is perpendicular to the curve
+    pub fn get_normal_foot_t(&self, p: Vector2) -> Vec<f64> {
+        let d = self.p1 - self.p0;
+        let dd = d.dot(d);
+        if dd == 0.0 {
+            return vec![];
+        }
+        let t = (p - self.p0).dot(d) / dd;
+        if (0.0..=1.0).contains(&t) {
+            vec![t]
+        } else {
+            vec![]
+        }
+    }
+
+    pub fn subcurve(&self, t0: f64, t1: f64) -> Self {
+        Self::new(self.lerp(t0), self.lerp(t1))
+    }
+}
+
+impl Lerpable for Line {
+    fn lerp(&self, t: f64) -> Vector2 {
+        lerp(&self.p0, &self.p1, t)
+    }
+
+    fn get_appro_length(&self, _split: usize) -> f64 {
+        (self.p1 - self.p0).norm()
+    }
+
+    fn get_appro_length_at_t(&self, t: f64, _split: usize) -> f64 {
+        self.get_appro_length(0) * t.clamp(0.0, 1.0)
+    }
+
+    fn get_appro_t_at_length(&self, length: f64, _split: usize) -> f64 {
+        let d = self.get_appro_length(0);
+        if d == 0.0 {
+            0.0
+        } else {
+            (length / d).clamp(0.0, 1.0)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Bezier2 {
-    p0: Vector2,
-    p1: Vector2,
-    p2: Vector2,
+    pub(crate) p0: Vector2,
+    pub(crate) p1: Vector2,
+    pub(crate) p2: Vector2,
 }
 
 impl Bezier2 {
     pub fn new(p0: Vector2, p1: Vector2, p2: Vector2) -> Self {
         Self { p0, p1, p2 }
     }
+
+    pub fn p0(&self) -> Vector2 {
+        self.p0
+    }
+
+    pub fn p1(&self) -> Vector2 {
+        self.p1
+    }
+
+    pub fn p2(&self) -> Vector2 {
+        self.p2
+    }
+
+    // de Casteljau
+    pub fn split_at(&self, t: f64) -> (Self, Self) {
+        let a = lerp(&self.p0, &self.p1, t);
+        let b = lerp(&self.p1, &self.p2, t);
+        let p = lerp(&a, &b, t);
+        (Self::new(self.p0, a, p), Self::new(p, b, self.p2))
+    }
+
+    // Parameters in (0, 1) where the projection onto `dir` turns
+    pub fn get_extrema_t(&self, dir: Vector2) -> Vec<f64> {
+        let a = (self.p1 - self.p0).dot(dir);
+        let b = (self.p2 - self.p1).dot(dir);
+        if a == b {
+            return vec![];
+        }
+        in_open_unit(vec![a / (a - b)])
+    }
+
+    pub fn derivative(&self, t: f64) -> Vector2 {
+        lerp(&(self.p1 - self.p0), &(self.p2 - self.p1), t).multi(2.0)
+    }
+
+    // Parameters in [0, 1] where `p - lerp(t)` is perpendicular to the curve
+    pub fn get_normal_foot_t(&self, p: Vector2) -> Vec<f64> {
+        get_normal_foot_t_polynomial(&self.get_power_coeffs(), p)
+    }
+
+    // Coefficients of t^0, t^1, t^2
+    pub(crate) fn get_power_coeffs(&self) -> [Vector2; 3] {
+        [
+            self.p0,
+            (self.p1 - self.p0).multi(2.0),
+            self.p0 - self.p1.multi(2.0) + self.p2,
+        ]
+    }
+
+    // Blossoming gives the control points of the part between t0 and t1 directly
+    pub fn subcurve(&self, t0: f64, t1: f64) -> Self {
+        Self::new(
+            self.blossom(t0, t0),
+            self.blossom(t0, t1),
+            self.blossom(t1, t1),
+        )
+    }
+
+    fn blossom(&self, u: f64, v: f64) -> Vector2 {
+        let a = lerp(&self.p0, &self.p1, u);
+        let b = lerp(&self.p1, &self.p2, u);
+        lerp(&a, &b, v)
+    }
 }
 
 impl Lerpable for Bezier2 {
@@ -127,17 +334,122 @@ impl Lerpable for Bezier2 {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Bezier3 {
-    p0: Vector2,
-    p1: Vector2,
-    p2: Vector2,
-    p3: Vector2,
+    pub(crate) p0: Vector2,
+    pub(crate) p1: Vector2,
+    pub(crate) p2: Vector2,
+    pub(crate) p3: Vector2,
 }
 
 impl Bezier3 {
     pub fn new(p0: Vector2, p1: Vector2, p2: Vector2, p3: Vector2) -> Self {
         Self { p0, p1, p2, p3 }
     }
+
+    pub fn p0(&self) -> Vector2 {
+        self.p0
+    }
+
+    pub fn p1(&self) -> Vector2 {
+        self.p1
+    }
+
+    pub fn p2(&self) -> Vector2 {
+        self.p2
+    }
+
+    pub fn p3(&self) -> Vector2 {
+        self.p3
+    }
+
+    // de Casteljau
+    pub fn split_at(&self, t: f64) -> (Self, Self) {
+        let a = lerp(&self.p0, &self.p1, t);
+        let b = lerp(&self.p1, &self.p2, t);
+        let c = lerp(&self.p2, &self.p3, t);
+        let ab = lerp(&a, &b, t);
+        let bc = lerp(&b, &c, t);
+        let p = lerp(&ab, &bc, t);
+        (Self::new(self.p0, a, ab, p), Self::new(p, bc, c, self.p3))
+    }
+
+    // Parameters in (0, 1) where the projection onto `dir` turns
+    pub fn get_extrema_t(&self, dir: Vector2) -> Vec<f64> {
+        let a = (self.p1 - self.p0).dot(dir);
+        let b = (self.p2 - self.p1).dot(dir);
+        let c = (self.p3 - self.p2).dot(dir);
+        in_open_unit(solve_quadratic(a - 2.0 * b + c, 2.0 * (b - a), a))
+    }
+
+    pub fn derivative(&self, t: f64) -> Vector2 {
+        let a = 1.0 - t;
+        ((self.p1 - self.p0).multi(a * a)
+            + (self.p2 - self.p1).multi(2.0 * a * t)
+            + (self.p3 - self.p2).multi(t * t))
+        .multi(3.0)
+    }
+
+    // Parameters in [0, 1] where `p - lerp(t)` is perpendicular to the curve
+    pub fn get_normal_foot_t(&self, p: Vector2) -> Vec<f64> {
+        get_normal_foot_t_polynomial(&self.get_power_coeffs(), p)
+    }
+
+    // Coefficients of t^0, t^1, t^2, t^3
+    pub(crate) fn get_power_coeffs(&self) -> [Vector2; 4] {
+        [
+            self.p0,
+            (self.p1 - self.p0).multi(3.0),
+            (self.p0 - self.p1.multi(2.0) + self.p2).multi(3.0),
+            self.p3 - self.p0 + (self.p1 - self.p2).multi(3.0),
+        ]
+    }
+
+    // Parameters of the loop's crossing point. Dividing P(s) - P(t) by s - t
+    // leaves a1 + a2(s + t) + a3((s + t)^2 - st) = 0, which is linear in
+    // s + t and st.
+    pub fn get_self_intersection_t(&self) -> Option<(f64, f64)> {
+        let [_, a1, a2, a3] = self.get_power_coeffs();
+        let det = a2.cross(a3);
+        let aa = a3.dot(a3);
+        if det == 0.0 || aa == 0.0 {
+            return None;
+        }
+
+        let sum = -a1.cross(a3) / det;
+        let product = sum * sum + (a1 + a2.multi(sum)).dot(a3) / aa;
+        let disc = sum * sum - 4.0 * product;
+        if disc <= 0.0 {
+            return None;
+        }
+
+        let s = (sum - disc.sqrt()) / 2.0;
+        let t = (sum + disc.sqrt()) / 2.0;
+        if 0.0 <= s && t <= 1.0 {
+            Some((s, t))
+        } else {
+            None
+        }
+    }
+
+    // Blossoming gives the control points of the part between t0 and t1 directly
+    pub fn subcurve(&self, t0: f64, t1: f64) -> Self {
+        Self::new(
+            self.blossom(t0, t0, t0),
+            self.blossom(t0, t0, t1),
+            self.blossom(t0, t1, t1),
+            self.blossom(t1, t1, t1),
+        )
+    }
+
+    fn blossom(&self, u: f64, v: f64, w: f64) -> Vector2 {
+        let a = lerp(&self.p0, &self.p1, u);
+        let b = lerp(&self.p1, &self.p2, u);
+        let c = lerp(&self.p2, &self.p3, u);
+        let ab = lerp(&a, &b, v);
+        let bc = lerp(&b, &c, v);
+        lerp(&ab, &bc, w)
+    }
 }
 
 impl Lerpable for Bezier3 {
@@ -152,15 +464,17 @@ impl Lerpable for Bezier3 {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Arc {
-    p0: Vector2,
-    rx: f64,
-    ry: f64,
-    p1: Vector2,
-
-    c: Vector2,
-    theta: f64,
-    dtheta: f64,
+    pub(crate) p0: Vector2,
+    pub(crate) rx: f64,
+    pub(crate) ry: f64,
+    pub(crate) p1: Vector2,
+
+    pub(crate) c: Vector2,
+    pub(crate) theta: f64,
+    pub(crate) dtheta: f64,
+    pub(crate) rotation: f64,
     sinr: f64,
     cosr: f64,
 }
@@ -191,7 +505,12 @@ impl Arc {
         let rx2 = rxa * rxa;
         let ry2 = rya * rya;
         let b = Vector2(rxa * a.1 / rya, -rya * a.0 / rxa)
-            .multi(((rx2 * ry2 - rx2 * ay2 - ry2 * ax2) / (rx2 * ay2 + ry2 * ax2)).sqrt())
+            // Rounding can make it slightly negative for scaled up radii
+            .multi(
+                ((rx2 * ry2 - rx2 * ay2 - ry2 * ax2) / (rx2 * ay2 + ry2 * ax2))
+                    .max(0.0)
+                    .sqrt(),
+            )
             .multi(if large == sweep { -1.0 } else { 1.0 });
 
         let c = b.rotate(r) + (p0 + p1).multi(0.5);
@@ -216,11 +535,184 @@ impl Arc {
             c,
             theta,
             dtheta,
+            rotation: r,
             sinr: r.sin(),
             cosr: r.cos(),
         }
     }
 
+    // Builds the arc from its center parameterization
+    pub(crate) fn from_center(
+        c: Vector2,
+        rx: f64,
+        ry: f64,
+        rotation: f64,
+        theta: f64,
+        dtheta: f64,
+    ) -> Self {
+        let mut ret = Self {
+            p0: c,
+            rx,
+            ry,
+            p1: c,
+            c,
+            theta,
+            dtheta,
+            rotation,
+            sinr: rotation.sin(),
+            cosr: rotation.cos(),
+        };
+        ret.p0 = ret.lerp(0.0);
+        ret.p1 = ret.lerp(1.0);
+        ret
+    }
+
+    pub fn p0(&self) -> Vector2 {
+        self.p0
+    }
+
+    pub fn p1(&self) -> Vector2 {
+        self.p1
+    }
+
+    pub fn center(&self) -> Vector2 {
+        self.c
+    }
+
+    pub fn rx(&self) -> f64 {
+        self.rx
+    }
+
+    pub fn ry(&self) -> f64 {
+        self.ry
+    }
+
+    // Radians, unlike the degrees passed to `new`
+    pub fn rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    pub fn theta(&self) -> f64 {
+        self.theta
+    }
+
+    pub fn dtheta(&self) -> f64 {
+        self.dtheta
+    }
+
+    pub fn split_at(&self, t: f64) -> (Self, Self) {
+        (self.subcurve(0.0, t), self.subcurve(t, 1.0))
+    }
+
+    // Parameters in (0, 1) where the projection onto `dir` turns
+    pub fn get_extrema_t(&self, dir: Vector2) -> Vec<f64> {
+        if self.rx == 0.0 || self.ry == 0.0 || self.dtheta == 0.0 {
+            return vec![];
+        }
+
+        // d/dr of dot(rotate((rx cos(r), ry sin(r))), dir) vanishes here
+        let d = Vector2(
+            self.cosr * dir.0 + self.sinr * dir.1,
+            -self.sinr * dir.0 + self.cosr * dir.1,
+        );
+        let r0 = (self.ry * d.1).atan2(self.rx * d.0);
+        let (lo, hi) = if self.dtheta > 0.0 {
+            (self.theta, self.theta + self.dtheta)
+        } else {
+            (self.theta + self.dtheta, self.theta)
+        };
+
+        let mut ret: Vec<f64> = vec![];
+        let mut r = r0 + ((lo - r0) / PI).ceil() * PI;
+        while r <= hi {
+            ret.push((r - self.theta) / self.dtheta);
+            r += PI;
+        }
+        in_open_unit(ret)
+    }
+
+    pub fn derivative(&self, t: f64) -> Vector2 {
+        if self.rx == 0.0 || self.ry == 0.0 {
+            return self.p1 - self.p0;
+        }
+
+        let r = self.theta + self.dtheta * t;
+        self.rotate(Vector2(-self.rx * r.sin(), self.ry * r.cos()))
+            .multi(self.dtheta)
+    }
+
+    // Parameters in [0, 1] where `p - lerp(t)` is perpendicular to the curve.
+    // The point is projected onto the ellipse in its own frame.
+    pub fn get_normal_foot_t(&self, p: Vector2) -> Vec<f64> {
+        if self.rx == 0.0 || self.ry == 0.0 {
+            return Line::new(self.p0, self.p1).get_normal_foot_t(p);
+        }
+        if self.dtheta == 0.0 {
+            return vec![];
+        }
+
+        let q = p - self.c;
+        let q = Vector2(
+            self.cosr * q.0 + self.sinr * q.1,
+            -self.sinr * q.0 + self.cosr * q.1,
+        );
+        let g = |t: f64| {
+            let r = self.theta + self.dtheta * t;
+            let (sin, cos) = r.sin_cos();
+            (self.ry * self.ry - self.rx * self.rx) * sin * cos + self.rx * q.0 * sin
+                - self.ry * q.1 * cos
+        };
+
+        let scale = (self.rx + self.ry) * (self.rx + self.ry + q.norm());
+        let is_zero = |v: f64| v.abs() <= scale * 1e-12;
+
+        // `g` has at most four roots in a turn
+        let n = ((self.dtheta.abs() / (PI / 16.0)).ceil() as usize).max(1);
+        let mut ret: Vec<f64> = vec![];
+        for i in 0..n {
+            let t0 = i as f64 / n as f64;
+            let t1 = (i + 1) as f64 / n as f64;
+            let (v0, v1) = (g(t0), g(t1));
+            if is_zero(v0) {
+                ret.push(t0);
+            } else if (v0 < 0.0) != (v1 < 0.0) && !is_zero(v1) {
+                ret.push(bisect(g, t0, t1));
+            }
+        }
+        if is_zero(g(1.0)) {
+            ret.push(1.0);
+        }
+        ret
+    }
+
+    pub fn subcurve(&self, t0: f64, t1: f64) -> Self {
+        let mut ret = if self.rx == 0.0 || self.ry == 0.0 {
+            Self {
+                p0: self.lerp(t0),
+                p1: self.lerp(t1),
+                ..*self
+            }
+        } else {
+            Self::from_center(
+                self.c,
+                self.rx,
+                self.ry,
+                self.rotation,
+                self.theta + self.dtheta * t0,
+                self.dtheta * (t1 - t0),
+            )
+        };
+
+        // Keep the original end points exact
+        if t0 == 0.0 {
+            ret.p0 = self.p0;
+        }
+        if t1 == 1.0 {
+            ret.p1 = self.p1;
+        }
+        ret
+    }
+
     fn rotate(&self, p: Vector2) -> Vector2 {
         Vector2(
             self.cosr * p.0 + -self.sinr * p.1,
@@ -241,7 +733,266 @@ impl Lerpable for Arc {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Curve {
+    Line(Line),
+    Bezier2(Bezier2),
+    Bezier3(Bezier3),
+    Arc(Arc),
+}
+
+impl Curve {
+    pub fn start(&self) -> Vector2 {
+        match self {
+            Curve::Line(c) => c.p0,
+            Curve::Bezier2(c) => c.p0,
+            Curve::Bezier3(c) => c.p0,
+            Curve::Arc(c) => c.p0,
+        }
+    }
+
+    pub fn end(&self) -> Vector2 {
+        match self {
+            Curve::Line(c) => c.p1,
+            Curve::Bezier2(c) => c.p2,
+            Curve::Bezier3(c) => c.p3,
+            Curve::Arc(c) => c.p1,
+        }
+    }
+
+    pub fn get_extrema_t(&self, dir: Vector2) -> Vec<f64> {
+        match self {
+            Curve::Line(_) => vec![],
+            Curve::Bezier2(c) => c.get_extrema_t(dir),
+            Curve::Bezier3(c) => c.get_extrema_t(dir),
+            Curve::Arc(c) => c.get_extrema_t(dir),
+        }
+    }
+
+    pub fn derivative(&self, t: f64) -> Vector2 {
+        match self {
+            Curve::Line(c) => c.derivative(t),
+            Curve::Bezier2(c) => c.derivative(t),
+            Curve::Bezier3(c) => c.derivative(t),
+            Curve::Arc(c) => c.derivative(t),
+        }
+    }
+
+    // Whether the curve stays within `tolerance` of its chord, judged by the
+    // control points for Bézier curves and the sagitta for arcs
+    pub fn is_flat(&self, tolerance: f64) -> bool {
+        let (p0, p1) = (self.start(), self.end());
+        let distance = |p: Vector2| {
+            let d = p1 - p0;
+            let dd = d.dot(d);
+            if dd == 0.0 {
+                return (p - p0).norm();
+            }
+            let t = ((p - p0).dot(d) / dd).clamp(0.0, 1.0);
+            (p - (p0 + d.multi(t))).norm()
+        };
+
+        match self {
+            Curve::Line(_) => true,
+            Curve::Bezier2(c) => distance(c.p1) <= tolerance,
+            Curve::Bezier3(c) => distance(c.p1) <= tolerance && distance(c.p2) <= tolerance,
+            Curve::Arc(c) => {
+                if c.rx == 0.0 || c.ry == 0.0 {
+                    return true;
+                }
+                let sagitta = c.rx.max(c.ry) * (1.0 - (c.dtheta.abs().min(PI) / 2.0).cos());
+                c.dtheta.abs() <= PI && sagitta <= tolerance
+            }
+        }
+    }
+
+    // Signed curvature, positive when turning from +x toward +y
+    pub fn get_curvature(&self, t: f64) -> f64 {
+        let d1 = self.derivative(t);
+        let d2 = match self {
+            Curve::Line(_) => return 0.0,
+            Curve::Bezier2(c) => c.get_power_coeffs()[2].multi(2.0),
+            Curve::Bezier3(c) => {
+                let [_, _, a2, a3] = c.get_power_coeffs();
+                a2.multi(2.0) + a3.multi(6.0 * t)
+            }
+            Curve::Arc(c) => {
+                if c.rx == 0.0 || c.ry == 0.0 {
+                    return 0.0;
+                }
+                let r = c.theta + c.dtheta * t;
+                c.rotate(Vector2(-c.rx * r.cos(), -c.ry * r.sin()))
+                    .multi(c.dtheta * c.dtheta)
+            }
+        };
+        let norm = d1.norm();
+        if norm == 0.0 {
+            0.0
+        } else {
+            d1.cross(d2) / (norm * norm * norm)
+        }
+    }
+
+    // The same curve traced from the end to the start
+    pub fn reverse(&self) -> Self {
+        match self {
+            Curve::Line(c) => Curve::Line(Line::new(c.p1, c.p0)),
+            Curve::Bezier2(c) => Curve::Bezier2(Bezier2::new(c.p2, c.p1, c.p0)),
+            Curve::Bezier3(c) => Curve::Bezier3(Bezier3::new(c.p3, c.p2, c.p1, c.p0)),
+            Curve::Arc(c) => Curve::Arc(Arc {
+                p0: c.p1,
+                p1: c.p0,
+                theta: c.theta + c.dtheta,
+                dtheta: -c.dtheta,
+                ..*c
+            }),
+        }
+    }
+
+    // Direction leaving the start point, looking past coincident control
+    // points. Zero when the curve is a single point.
+    pub fn get_start_tangent(&self) -> Vector2 {
+        let candidates = match self {
+            Curve::Line(c) => vec![c.p1 - c.p0],
+            Curve::Bezier2(c) => vec![c.p1 - c.p0, c.p2 - c.p0],
+            Curve::Bezier3(c) => vec![c.p1 - c.p0, c.p2 - c.p0, c.p3 - c.p0],
+            Curve::Arc(c) => vec![c.derivative(0.0)],
+        };
+        first_non_zero(candidates)
+    }
+
+    // Direction arriving at the end point, looking past coincident control
+    // points. Zero when the curve is a single point.
+    pub fn get_end_tangent(&self) -> Vector2 {
+        let candidates = match self {
+            Curve::Line(c) => vec![c.p1 - c.p0],
+            Curve::Bezier2(c) => vec![c.p2 - c.p1, c.p2 - c.p0],
+            Curve::Bezier3(c) => vec![c.p3 - c.p2, c.p3 - c.p1, c.p3 - c.p0],
+            Curve::Arc(c) => vec![c.derivative(1.0)],
+        };
+        first_non_zero(candidates)
+    }
+
+    pub fn get_normal_foot_t(&self, p: Vector2) -> Vec<f64> {
+        match self {
+            Curve::Line(c) => c.get_normal_foot_t(p),
+            Curve::Bezier2(c) => c.get_normal_foot_t(p),
+            Curve::Bezier3(c) => c.get_normal_foot_t(p),
+            Curve::Arc(c) => c.get_normal_foot_t(p),
+        }
+    }
+
+    // Exact axis-aligned bounding box as `(min, max)`
+    pub fn get_bounds(&self) -> (Vector2, Vector2) {
+        let mut points = vec![self.start(), self.end()];
+        for dir in [Vector2(1.0, 0.0), Vector2(0.0, 1.0)] {
+            points.extend(self.get_extrema_t(dir).iter().map(|t| self.lerp(*t)));
+        }
+        points.iter().fold(
+            (
+                Vector2(f64::INFINITY, f64::INFINITY),
+                Vector2(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), p| {
+                (
+                    Vector2(min.0.min(p.0), min.1.min(p.1)),
+                    Vector2(max.0.max(p.0), max.1.max(p.1)),
+                )
+            },
+        )
+    }
+
+    // Parameter of the nearest point on the curve to `p`
+    pub fn get_closest_t(&self, p: Vector2) -> f64 {
+        let mut ts = vec![0.0, 1.0];
+        ts.extend(self.get_normal_foot_t(p));
+        ts.into_iter()
+            .map(|t| (t, (self.lerp(t) - p).norm()))
+            .fold(
+                (0.0, f64::INFINITY),
+                |acc, v| if v.1 < acc.1 { v } else { acc },
+            )
+            .0
+    }
+
+    pub fn split_at(&self, t: f64) -> (Self, Self) {
+        match self {
+            Curve::Line(c) => {
+                let (a, b) = c.split_at(t);
+                (Curve::Line(a), Curve::Line(b))
+            }
+            Curve::Bezier2(c) => {
+                let (a, b) = c.split_at(t);
+                (Curve::Bezier2(a), Curve::Bezier2(b))
+            }
+            Curve::Bezier3(c) => {
+                let (a, b) = c.split_at(t);
+                (Curve::Bezier3(a), Curve::Bezier3(b))
+            }
+            Curve::Arc(c) => {
+                let (a, b) = c.split_at(t);
+                (Curve::Arc(a), Curve::Arc(b))
+            }
+        }
+    }
+
+    pub fn subcurve(&self, t0: f64, t1: f64) -> Self {
+        match self {
+            Curve::Line(c) => Curve::Line(c.subcurve(t0, t1)),
+            Curve::Bezier2(c) => Curve::Bezier2(c.subcurve(t0, t1)),
+            Curve::Bezier3(c) => Curve::Bezier3(c.subcurve(t0, t1)),
+            Curve::Arc(c) => Curve::Arc(c.subcurve(t0, t1)),
+        }
+    }
+}
+
+fn first_non_zero(candidates: Vec<Vector2>) -> Vector2 {
+    candidates
+        .into_iter()
+        .find(|v| *v != Vector2(0.0, 0.0))
+        .unwrap_or(Vector2(0.0, 0.0))
+}
+
+impl Lerpable for Curve {
+    fn lerp(&self, t: f64) -> Vector2 {
+        match self {
+            Curve::Line(c) => c.lerp(t),
+            Curve::Bezier2(c) => c.lerp(t),
+            Curve::Bezier3(c) => c.lerp(t),
+            Curve::Arc(c) => c.lerp(t),
+        }
+    }
+
+    fn get_appro_length(&self, split: usize) -> f64 {
+        match self {
+            Curve::Line(c) => c.get_appro_length(split),
+            Curve::Bezier2(c) => c.get_appro_length(split),
+            Curve::Bezier3(c) => c.get_appro_length(split),
+            Curve::Arc(c) => c.get_appro_length(split),
+        }
+    }
+
+    fn get_appro_length_at_t(&self, t: f64, split: usize) -> f64 {
+        match self {
+            Curve::Line(c) => c.get_appro_length_at_t(t, split),
+            Curve::Bezier2(c) => c.get_appro_length_at_t(t, split),
+            Curve::Bezier3(c) => c.get_appro_length_at_t(t, split),
+            Curve::Arc(c) => c.get_appro_length_at_t(t, split),
+        }
+    }
+
+    fn get_appro_t_at_length(&self, length: f64, split: usize) -> f64 {
+        match self {
+            Curve::Line(c) => c.get_appro_t_at_length(length, split),
+            Curve::Bezier2(c) => c.get_appro_t_at_length(length, split),
+            Curve::Bezier3(c) => c.get_appro_t_at_length(length, split),
+            Curve::Arc(c) => c.get_appro_t_at_length(length, split),
+        }
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::get_first, clippy::useless_vec)]
 mod tests {
     use super::*;
 
@@ -282,6 +1033,13 @@ mod tests {
         assert_eq!(p.radian(Vector2(0.0, -1.0)), -PI * 0.5);
     }
 
+    #[test]
+    fn vector2_radian_antiparallel() {
+        let p = Vector2(1.0606601717798212, 1.0606601717798212);
+        assert_eq!(p.radian(p.multi(-1.0)).abs(), PI);
+        assert_eq!(p.radian(p.multi(3.0)), 0.0);
+    }
+
     #[test]
     fn get_polyline_length_cases() {
         let p0 = Vector2(0.0, 0.0);
@@ -473,4 +1231,398 @@ mod tests {
         assert_eq!(s1.get(3).unwrap().0.round(), 150.0);
         assert_eq!(s1.get(3).unwrap().1.round(), 150.0);
     }
+
+    #[test]
+    fn arc_scaled_up_radii() {
+        let target = Arc::new(
+            Vector2(0.0, 0.0),
+            1.0,
+            2.0,
+            30.0,
+            false,
+            true,
+            Vector2(0.37, 2.73),
+        );
+        assert!(!target.get_appro_length(20).is_nan());
+        assert!((target.lerp(1.0) - Vector2(0.37, 2.73)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn get_appro_t_at_length_cases() {
+        let target = Bezier2::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0), Vector2(10.0, 10.0));
+        let length = target.get_appro_length(4);
+        assert_eq!(target.get_appro_t_at_length(-1.0, 4), 0.0);
+        assert_eq!(target.get_appro_t_at_length(0.0, 4), 0.0);
+        assert_eq!(target.get_appro_t_at_length(length, 4), 1.0);
+        assert_eq!(target.get_appro_t_at_length(length + 1.0, 4), 1.0);
+        let first = (Vector2(4.375, 0.625) - Vector2(0.0, 0.0)).norm();
+        assert_eq!(target.get_appro_t_at_length(first, 4), 0.25);
+        assert_eq!(target.get_appro_t_at_length(first / 2.0, 4), 0.125);
+    }
+
+    #[test]
+    fn line_subcurve_cases() {
+        let target = Line::new(Vector2(0.0, 0.0), Vector2(10.0, 20.0));
+        assert_eq!(
+            target.subcurve(0.25, 0.5),
+            Line::new(Vector2(2.5, 5.0), Vector2(5.0, 10.0))
+        );
+    }
+
+    #[test]
+    fn bezier_q_subcurve_cases() {
+        let target = Bezier2::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0), Vector2(10.0, 10.0));
+        assert_eq!(target.subcurve(0.0, 1.0), target);
+        assert_eq!(
+            target.subcurve(0.0, 0.5),
+            Bezier2::new(Vector2(0.0, 0.0), Vector2(5.0, 0.0), Vector2(7.5, 2.5))
+        );
+        assert_eq!(
+            target.subcurve(0.5, 1.0),
+            Bezier2::new(Vector2(7.5, 2.5), Vector2(10.0, 5.0), Vector2(10.0, 10.0))
+        );
+    }
+
+    #[test]
+    fn bezier_c_subcurve_cases() {
+        let target = Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(10.0, 0.0),
+            Vector2(0.0, 10.0),
+            Vector2(10.0, 10.0),
+        );
+        assert_eq!(target.subcurve(0.0, 1.0), target);
+        let s = target.subcurve(0.25, 0.75);
+        assert_eq!(s.p0, target.lerp(0.25));
+        assert_eq!(s.p3, target.lerp(0.75));
+        assert_eq!(s.lerp(0.5), target.lerp(0.5));
+    }
+
+    #[test]
+    fn arc_subcurve_cases() {
+        let target = Arc::new(
+            Vector2(0.0, 0.0),
+            5.0,
+            5.0,
+            0.0,
+            false,
+            true,
+            Vector2(5.0, 5.0),
+        );
+        let s = target.subcurve(0.5, 1.0);
+        assert_eq!(s.c, target.c);
+        assert_eq!(s.rx, 5.0);
+        assert_eq!(s.ry, 5.0);
+        assert_eq!(s.p0, target.lerp(0.5));
+        assert_eq!(s.dtheta, target.dtheta * 0.5);
+        assert_eq!(s.p1.0.round(), 5.0);
+        assert_eq!(s.p1.1.round(), 5.0);
+    }
+
+    #[test]
+    fn bezier_q_split_at_cases() {
+        let target = Bezier2::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0), Vector2(10.0, 10.0));
+        let (a, b) = target.split_at(0.5);
+        assert_eq!(a.p0(), Vector2(0.0, 0.0));
+        assert_eq!(a.p1(), Vector2(5.0, 0.0));
+        assert_eq!(a.p2(), Vector2(7.5, 2.5));
+        assert_eq!(b.p0(), Vector2(7.5, 2.5));
+        assert_eq!(b.p1(), Vector2(10.0, 5.0));
+        assert_eq!(b.p2(), Vector2(10.0, 10.0));
+        assert_eq!(
+            (a, b),
+            (target.subcurve(0.0, 0.5), target.subcurve(0.5, 1.0))
+        );
+    }
+
+    #[test]
+    fn bezier_c_split_at_cases() {
+        let target = Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(10.0, 0.0),
+            Vector2(0.0, 10.0),
+            Vector2(10.0, 10.0),
+        );
+        let (a, b) = target.split_at(0.5);
+        assert_eq!(a.p0(), Vector2(0.0, 0.0));
+        assert_eq!(a.p1(), Vector2(5.0, 0.0));
+        assert_eq!(a.p2(), Vector2(5.0, 2.5));
+        assert_eq!(a.p3(), Vector2(5.0, 5.0));
+        assert_eq!(b.p0(), Vector2(5.0, 5.0));
+        assert_eq!(b.p1(), Vector2(5.0, 7.5));
+        assert_eq!(b.p2(), Vector2(5.0, 10.0));
+        assert_eq!(b.p3(), Vector2(10.0, 10.0));
+        assert_eq!(a.lerp(0.5), target.lerp(0.25));
+        assert_eq!(b.lerp(0.5), target.lerp(0.75));
+    }
+
+    #[test]
+    fn arc_split_at_cases() {
+        let target = Arc::new(
+            Vector2(0.0, 0.0),
+            10.0,
+            5.0,
+            30.0,
+            true,
+            false,
+            Vector2(5.0, 5.0),
+        );
+        let (a, b) = target.split_at(0.25);
+        assert_eq!(a.p0(), target.p0());
+        assert_eq!(b.p1(), target.p1());
+        assert_eq!(a.p1(), b.p0());
+        for s in [a, b] {
+            assert_eq!(s.center(), target.center());
+            assert_eq!(s.rx(), target.rx());
+            assert_eq!(s.ry(), target.ry());
+            assert_eq!(s.rotation(), 30.0_f64.to_radians());
+        }
+        assert_eq!(a.theta(), target.theta());
+        assert_eq!(a.dtheta(), target.dtheta() * 0.25);
+        assert_eq!(b.theta(), target.theta() + target.dtheta() * 0.25);
+        assert_eq!(b.dtheta(), target.dtheta() * 0.75);
+    }
+
+    #[test]
+    fn curve_split_at_cases() {
+        let target = Curve::Line(Line::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0)));
+        assert_eq!(
+            target.split_at(0.3),
+            (
+                Curve::Line(Line::new(Vector2(0.0, 0.0), Vector2(3.0, 0.0))),
+                Curve::Line(Line::new(Vector2(3.0, 0.0), Vector2(10.0, 0.0)))
+            )
+        );
+    }
+
+    #[test]
+    fn get_extrema_t_cases() {
+        let q = Bezier2::new(Vector2(0.0, 0.0), Vector2(5.0, 10.0), Vector2(10.0, 0.0));
+        assert_eq!(q.get_extrema_t(Vector2(0.0, 1.0)), vec![0.5]);
+        assert_eq!(q.get_extrema_t(Vector2(1.0, 0.0)), vec![]);
+
+        let c = Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(0.0, -10.0),
+            Vector2(10.0, -10.0),
+            Vector2(10.0, 0.0),
+        );
+        assert_eq!(c.get_extrema_t(Vector2(0.0, 1.0)), vec![0.5]);
+
+        // Half circle from (10, 0) to (-10, 0) through (0, 10)
+        let a = Arc::new(
+            Vector2(10.0, 0.0),
+            10.0,
+            10.0,
+            0.0,
+            false,
+            true,
+            Vector2(-10.0, 0.0),
+        );
+        let ts = a.get_extrema_t(Vector2(0.0, 1.0));
+        assert_eq!(ts.len(), 1);
+        assert!((ts[0] - 0.5).abs() < 1e-12);
+        assert_eq!(a.get_extrema_t(Vector2(1.0, 0.0)), vec![]);
+    }
+
+    #[test]
+    fn derivative_cases() {
+        let q = Bezier2::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0), Vector2(10.0, 10.0));
+        assert_eq!(q.derivative(0.0), Vector2(20.0, 0.0));
+        assert_eq!(q.derivative(1.0), Vector2(0.0, 20.0));
+
+        let c = Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(10.0, 0.0),
+            Vector2(0.0, 10.0),
+            Vector2(10.0, 10.0),
+        );
+        assert_eq!(c.derivative(0.0), Vector2(30.0, 0.0));
+        assert_eq!(c.derivative(0.5), Vector2(0.0, 15.0));
+
+        let a = Arc::new(
+            Vector2(10.0, 0.0),
+            10.0,
+            10.0,
+            0.0,
+            false,
+            true,
+            Vector2(-10.0, 0.0),
+        );
+        let d = a.derivative(0.0);
+        assert_eq!(d.0.round(), 0.0);
+        assert_eq!(d.1.round(), (10.0 * PI).round());
+    }
+
+    #[test]
+    fn curve_tangent_cases() {
+        let p = Vector2(1.0, 1.0);
+        let c = Curve::Bezier3(Bezier3::new(p, p, Vector2(5.0, 1.0), Vector2(5.0, 5.0)));
+        assert_eq!(c.get_start_tangent(), Vector2(4.0, 0.0));
+        assert_eq!(c.get_end_tangent(), Vector2(0.0, 4.0));
+        let dot = Curve::Bezier3(Bezier3::new(p, p, p, p));
+        assert_eq!(dot.get_start_tangent(), Vector2(0.0, 0.0));
+        assert_eq!(dot.get_end_tangent(), Vector2(0.0, 0.0));
+    }
+
+    #[test]
+    fn get_normal_foot_t_cases() {
+        let l = Line::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0));
+        assert_eq!(l.get_normal_foot_t(Vector2(3.0, 5.0)), vec![0.3]);
+        assert_eq!(l.get_normal_foot_t(Vector2(-3.0, 5.0)), vec![]);
+
+        let q = Bezier2::new(Vector2(0.0, 0.0), Vector2(5.0, 10.0), Vector2(10.0, 0.0));
+        let ts = q.get_normal_foot_t(Vector2(5.0, 20.0));
+        assert_eq!(ts.len(), 1);
+        assert!((ts[0] - 0.5).abs() < 1e-9);
+
+        let c = Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(0.0, 10.0),
+            Vector2(10.0, 10.0),
+            Vector2(10.0, 0.0),
+        );
+        let ts = c.get_normal_foot_t(Vector2(5.0, 7.0));
+        assert_eq!(ts.len(), 1);
+        assert!((ts[0] - 0.5).abs() < 1e-9);
+        // Both ends are perpendicular from there as well
+        let ts = c.get_normal_foot_t(Vector2(5.0, 0.0));
+        assert_eq!(ts.len(), 3);
+        assert!(ts[0].abs() < 1e-9);
+        assert!((ts[1] - 0.5).abs() < 1e-9);
+        assert!((ts[2] - 1.0).abs() < 1e-9);
+
+        let a = Arc::new(
+            Vector2(10.0, 0.0),
+            10.0,
+            10.0,
+            0.0,
+            false,
+            true,
+            Vector2(-10.0, 0.0),
+        );
+        let ts = a.get_normal_foot_t(Vector2(5.0, 5.0));
+        assert_eq!(ts.len(), 1);
+        assert!((ts[0] - 0.25).abs() < 1e-9);
+        let e = Arc::new(
+            Vector2(20.0, 0.0),
+            20.0,
+            10.0,
+            0.0,
+            false,
+            true,
+            Vector2(-20.0, 0.0),
+        );
+        let ts = e.get_normal_foot_t(Vector2(0.0, 30.0));
+        assert_eq!(ts.len(), 1);
+        assert!((ts[0] - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn get_appro_length_at_t_cases() {
+        let target = Bezier2::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0), Vector2(10.0, 10.0));
+        let length = target.get_appro_length(4);
+        assert_eq!(target.get_appro_length_at_t(0.0, 4), 0.0);
+        assert_eq!(target.get_appro_length_at_t(1.0, 4), length);
+        for d in [1.0, 5.0, 12.0] {
+            let t = target.get_appro_t_at_length(d, 4);
+            assert!((target.get_appro_length_at_t(t, 4) - d).abs() < 1e-12);
+        }
+
+        let line = Line::new(Vector2(0.0, 0.0), Vector2(3.0, 4.0));
+        assert_eq!(line.get_appro_length_at_t(0.5, 4), 2.5);
+    }
+
+    #[test]
+    fn get_closest_t_cases() {
+        let l = Curve::Line(Line::new(Vector2(0.0, 0.0), Vector2(10.0, 0.0)));
+        assert_eq!(l.get_closest_t(Vector2(3.0, 5.0)), 0.3);
+        assert_eq!(l.get_closest_t(Vector2(-3.0, 5.0)), 0.0);
+        assert_eq!(l.get_closest_t(Vector2(13.0, 5.0)), 1.0);
+
+        let c = Curve::Bezier3(Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(0.0, 10.0),
+            Vector2(10.0, 10.0),
+            Vector2(10.0, 0.0),
+        ));
+        assert!((c.get_closest_t(Vector2(5.0, 20.0)) - 0.5).abs() < 1e-9);
+        assert_eq!(c.get_closest_t(Vector2(-1.0, -1.0)), 0.0);
+    }
+
+    #[test]
+    fn get_self_intersection_t_cases() {
+        let c = Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(20.0, 10.0),
+            Vector2(-10.0, 10.0),
+            Vector2(10.0, 0.0),
+        );
+        let (s, t) = c.get_self_intersection_t().unwrap();
+        assert!(s < t);
+        assert!((c.lerp(s) - c.lerp(t)).norm() < 1e-9);
+
+        let c = Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(0.0, 10.0),
+            Vector2(10.0, 10.0),
+            Vector2(10.0, 0.0),
+        );
+        assert_eq!(c.get_self_intersection_t(), None);
+    }
+
+    #[test]
+    fn get_curvature_cases() {
+        let circle = Curve::Arc(Arc::new(
+            Vector2(10.0, 0.0),
+            10.0,
+            10.0,
+            0.0,
+            false,
+            true,
+            Vector2(-10.0, 0.0),
+        ));
+        assert!((circle.get_curvature(0.3) - 0.1).abs() < 1e-12);
+        assert!((circle.reverse().get_curvature(0.3) + 0.1).abs() < 1e-12);
+        assert_eq!(circle.reverse().start(), Vector2(-10.0, 0.0));
+        assert!((circle.reverse().lerp(0.25) - circle.lerp(0.75)).norm() < 1e-12);
+
+        let c = Curve::Bezier3(Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(0.0, -10.0),
+            Vector2(10.0, -10.0),
+            Vector2(10.0, 0.0),
+        ));
+        assert!(c.get_curvature(0.5) > 0.0);
+        assert_eq!(c.get_curvature(0.5), -c.reverse().get_curvature(0.5));
+        assert_eq!(
+            Curve::Line(Line::new(Vector2(0.0, 0.0), Vector2(1.0, 1.0))).get_curvature(0.5),
+            0.0
+        );
+    }
+
+    #[test]
+    fn get_bounds_cases() {
+        let c = Curve::Bezier3(Bezier3::new(
+            Vector2(0.0, 0.0),
+            Vector2(0.0, -10.0),
+            Vector2(10.0, -10.0),
+            Vector2(10.0, 0.0),
+        ));
+        assert_eq!(c.get_bounds(), (Vector2(0.0, -7.5), Vector2(10.0, 0.0)));
+
+        let a = Curve::Arc(Arc::new(
+            Vector2(10.0, 0.0),
+            10.0,
+            10.0,
+            0.0,
+            false,
+            true,
+            Vector2(-10.0, 0.0),
+        ));
+        let (min, max) = a.get_bounds();
+        assert_eq!(min, Vector2(-10.0, 0.0));
+        assert_eq!(max.0, 10.0);
+        assert!((max.1 - 10.0).abs() < 1e-12);
+    }
 }